@@ -0,0 +1,414 @@
+use std::io;
+use std::time::{Duration, Instant, SystemTime};
+
+use reqwest::header;
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::config::Config;
+use crate::util::{url, RouxError, StoredToken};
+
+const INSTALLED_CLIENT_GRANT: &str = "https://oauth.reddit.com/grants/installed_client";
+
+/// Tokens are refreshed this long before Reddit says they expire.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum AuthResponse {
+    AuthData {
+        access_token: String,
+        refresh_token: Option<String>,
+        scope: Option<String>,
+        expires_in: Option<u64>,
+    },
+    ErrorData {
+        error: String,
+    },
+}
+
+/// The ways an access token can be requested.
+#[derive(Debug, Clone)]
+pub enum Grant {
+    Password,
+    ClientCredentials,
+    InstalledClient,
+    RefreshToken,
+    AuthorizationCode(String),
+}
+
+impl Grant {
+    /// The grant a user login can use with the credentials in `config`, if any.
+    pub fn from_config(config: &Config) -> Option<Grant> {
+        if config.username.is_some() && config.password.is_some() {
+            Some(Grant::Password)
+        } else if config.refresh_token.is_some() {
+            Some(Grant::RefreshToken)
+        } else {
+            None
+        }
+    }
+
+    /// The grant to use to get a new token once the current one expires, if there is one.
+    pub fn renewal(&self, config: &Config) -> Option<Grant> {
+        match self {
+            // App-only logins never act as a user, whatever refresh token is configured.
+            Grant::ClientCredentials => Some(Grant::ClientCredentials),
+            Grant::InstalledClient => Some(Grant::InstalledClient),
+            _ if config.refresh_token.is_some() => Some(Grant::RefreshToken),
+            // A fixed one-time code only works once, so the password cannot be sent again.
+            Grant::Password if config.otp_spent => None,
+            Grant::Password => Some(Grant::Password),
+            Grant::RefreshToken | Grant::AuthorizationCode(_) => None,
+        }
+    }
+
+    /// Whether this grant logs in as a user rather than as the app.
+    fn is_user(&self) -> bool {
+        !matches!(self, Grant::ClientCredentials | Grant::InstalledClient)
+    }
+
+    /// The form to send for this grant, or `None` if `config` is missing a credential.
+    fn form(&self, config: &Config) -> Option<Vec<(&'static str, String)>> {
+        Some(match self {
+            Grant::Password => {
+                // Reddit takes the one-time code appended to the password.
+                let password = match config.otp() {
+                    Some(otp) => format!("{}:{}", config.password.as_ref()?, otp),
+                    // The code was used up by the first login, there is no new one to send.
+                    None if config.otp_spent => return None,
+                    None => config.password.to_owned()?,
+                };
+
+                vec![
+                    ("grant_type", "password".to_owned()),
+                    ("username", config.username.to_owned()?),
+                    ("password", password),
+                ]
+            }
+            Grant::ClientCredentials => vec![("grant_type", "client_credentials".to_owned())],
+            Grant::InstalledClient => vec![
+                ("grant_type", INSTALLED_CLIENT_GRANT.to_owned()),
+                ("device_id", config.device_id.to_owned()?),
+            ],
+            Grant::RefreshToken => vec![
+                ("grant_type", "refresh_token".to_owned()),
+                ("refresh_token", config.refresh_token.to_owned()?),
+            ],
+            Grant::AuthorizationCode(code) => vec![
+                ("grant_type", "authorization_code".to_owned()),
+                ("code", code.to_owned()),
+                ("redirect_uri", config.redirect_uri.to_owned()?),
+            ],
+        })
+    }
+}
+
+/// An access token and what Reddit told us about it.
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub scopes: Option<Vec<String>>,
+    pub expires_at: Option<Instant>,
+}
+
+impl Token {
+    /// A token from somewhere other than a grant, so we know nothing about its expiry.
+    pub fn new(access_token: &str) -> Token {
+        Token {
+            access_token: access_token.to_owned(),
+            refresh_token: None,
+            scopes: None,
+            expires_at: None,
+        }
+    }
+
+    /// A token loaded from a [`TokenStore`](crate::util::TokenStore).
+    pub fn from_stored(stored: &StoredToken) -> Token {
+        Token {
+            access_token: stored.access_token.to_owned(),
+            refresh_token: stored.refresh_token.to_owned(),
+            scopes: Some(stored.scopes.to_owned()),
+            expires_at: stored
+                .expires_in()
+                .map(|secs| Instant::now() + Duration::from_secs(secs)),
+        }
+    }
+
+    /// Whether the token has expired or is about to.
+    pub fn expires_soon(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => Instant::now() + EXPIRY_MARGIN >= expires_at,
+            None => false,
+        }
+    }
+
+    /// Copy what this token changed into the config.
+    pub fn apply(&self, config: &mut Config) {
+        config.access_token = Some(self.access_token.to_owned());
+
+        if self.refresh_token.is_some() {
+            config.refresh_token = self.refresh_token.to_owned();
+        }
+
+        if let Some(scopes) = &self.scopes {
+            config.scopes = scopes.to_owned();
+        }
+    }
+}
+
+/// The key the token for a `grant` login is stored under. App-only logins and user logins
+/// are kept apart, so neither can restore the other's token. A user login whose username is
+/// not known yet has no key, its token is saved once the username is looked up.
+fn store_key(config: &Config, grant: &Grant) -> Option<String> {
+    let who = match grant {
+        Grant::ClientCredentials => "app".to_owned(),
+        Grant::InstalledClient => format!("device:{}", config.device_id.as_ref()?),
+        _ => format!("user:{}", config.username.as_ref()?),
+    };
+
+    Some(format!("{}:{}", config.client_id, who))
+}
+
+/// Load the token for this login from the config's token store, if it has one.
+pub fn load_token(config: &Config, grant: &Grant) -> io::Result<Option<Token>> {
+    match (&config.token_store, store_key(config, grant)) {
+        (Some(store), Some(key)) => Ok(store.load(&key)?.map(|stored| Token::from_stored(&stored))),
+        _ => Ok(None),
+    }
+}
+
+/// Save the token for this login to the config's token store, if it has one.
+pub fn save_token(config: &Config, grant: &Grant, token: &Token) -> io::Result<()> {
+    let (store, key) = match (&config.token_store, store_key(config, grant)) {
+        (Some(store), Some(key)) => (store, key),
+        _ => return Ok(()),
+    };
+
+    let stored = StoredToken {
+        access_token: token.access_token.to_owned(),
+        refresh_token: token
+            .refresh_token
+            .to_owned()
+            .or_else(|| config.refresh_token.to_owned().filter(|_| grant.is_user())),
+        scopes: token
+            .scopes
+            .to_owned()
+            .unwrap_or_else(|| config.scopes.to_owned()),
+        expires_at: token.expires_at.map(|expires_at| {
+            let expires_at =
+                SystemTime::now() + expires_at.saturating_duration_since(Instant::now());
+            expires_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        }),
+    };
+
+    store.save(&key, &stored)
+}
+
+/// Request a new access token from Reddit.
+#[maybe_async::maybe_async]
+pub async fn request_token(
+    client: &Client,
+    config: &Config,
+    grant: &Grant,
+) -> Result<Token, RouxError> {
+    let url = &url::build(&config.base_url, "api/v1/access_token")[..];
+    if matches!(grant, Grant::Password) {
+        config.check_otp()?;
+    }
+    let form = grant.form(config).ok_or(RouxError::CredentialsNotSet)?;
+
+    let response = client
+        .post(url)
+        .header(USER_AGENT, &config.user_agent[..])
+        .basic_auth(&config.client_id, Some(&config.client_secret))
+        .form(&form)
+        .send()
+        .await?;
+
+    if response.status() == 200 {
+        match response.json::<AuthResponse>().await? {
+            AuthResponse::AuthData {
+                access_token,
+                refresh_token,
+                scope,
+                expires_in,
+            } => Ok(Token {
+                access_token,
+                refresh_token,
+                scopes: scope.map(|scope| scope.split_whitespace().map(String::from).collect()),
+                expires_at: expires_in.map(|secs| Instant::now() + Duration::from_secs(secs)),
+            }),
+            AuthResponse::ErrorData { error } => Err(auth_error(error, config, grant)),
+        }
+    } else {
+        Err(RouxError::from_response(response).await)
+    }
+}
+
+/// Reddit does not say why a password login failed, so when a one-time code was sent and
+/// the grant is rejected the code is the likely culprit.
+fn auth_error(error: String, config: &Config, grant: &Grant) -> RouxError {
+    let lower = error.to_lowercase();
+    let mentions_otp = ["otp", "2fa", "two-factor", "two_factor"]
+        .iter()
+        .any(|s| lower.contains(s));
+    let rejected_otp =
+        matches!(grant, Grant::Password) && config.otp().is_some() && error == "invalid_grant";
+
+    if mentions_otp || rejected_otp {
+        RouxError::TwoFactor(error)
+    } else {
+        RouxError::Auth(error)
+    }
+}
+
+/// Build a client that sends the user agent with every request. The access token is not a
+/// default header: the session adds the current one to each request, as it changes on renewal.
+pub fn session_client(config: &Config) -> Client {
+    let mut headers = header::HeaderMap::new();
+
+    headers.insert(
+        header::USER_AGENT,
+        header::HeaderValue::from_str(&config.user_agent[..]).unwrap(),
+    );
+
+    Client::builder().default_headers(headers).build().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use std::sync::Arc;
+
+    use super::{auth_error, load_token, save_token, Grant, Token};
+    use crate::config::Config;
+    use crate::util::{MemoryTokenStore, RouxError};
+
+    #[test]
+    fn test_expires_soon() {
+        let mut token = Token::new("token");
+        assert!(!token.expires_soon());
+
+        token.expires_at = Some(Instant::now() + Duration::from_secs(3600));
+        assert!(!token.expires_soon());
+
+        token.expires_at = Some(Instant::now() + Duration::from_secs(30));
+        assert!(token.expires_soon());
+    }
+
+    #[test]
+    fn test_renewal() {
+        let mut config = Config::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET");
+
+        assert!(matches!(
+            Grant::Password.renewal(&config),
+            Some(Grant::Password)
+        ));
+        assert!(Grant::AuthorizationCode("code".to_owned())
+            .renewal(&config)
+            .is_none());
+
+        config.refresh_token = Some("refresh".to_owned());
+        assert!(matches!(
+            Grant::AuthorizationCode("code".to_owned()).renewal(&config),
+            Some(Grant::RefreshToken)
+        ));
+        assert!(matches!(
+            Grant::ClientCredentials.renewal(&config),
+            Some(Grant::ClientCredentials)
+        ));
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let mut config = Config::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET");
+        config.username = Some("USERNAME".to_owned());
+        config.refresh_token = Some("refresh".to_owned());
+        config.token_store = Some(Arc::new(MemoryTokenStore::new()));
+
+        assert!(load_token(&config, &Grant::Password).unwrap().is_none());
+
+        let mut token = Token::new("access");
+        token.expires_at = Some(Instant::now() + Duration::from_secs(3600));
+        save_token(&config, &Grant::Password, &token).unwrap();
+
+        let restored = load_token(&config, &Grant::RefreshToken).unwrap().unwrap();
+        assert_eq!(restored.access_token, "access");
+        assert_eq!(restored.refresh_token, Some("refresh".to_owned()));
+        assert!(!restored.expires_soon());
+
+        // App-only logins never see a user's token, nor a user login an app token.
+        assert!(load_token(&config, &Grant::ClientCredentials)
+            .unwrap()
+            .is_none());
+        save_token(&config, &Grant::ClientCredentials, &Token::new("app")).unwrap();
+        let restored = load_token(&config, &Grant::ClientCredentials)
+            .unwrap()
+            .unwrap();
+        assert_eq!(restored.refresh_token, None);
+        assert_eq!(
+            load_token(&config, &Grant::Password)
+                .unwrap()
+                .unwrap()
+                .access_token,
+            "access"
+        );
+
+        config.username = Some("OTHER".to_owned());
+        assert!(load_token(&config, &Grant::Password).unwrap().is_none());
+
+        // A user login that does not know its username yet has no key.
+        config.username = None;
+        save_token(&config, &Grant::RefreshToken, &Token::new("unknown")).unwrap();
+        assert!(load_token(&config, &Grant::RefreshToken).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_otp() {
+        let mut config = Config::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET");
+        config.username = Some("USERNAME".to_owned());
+        config.password = Some("PASSWORD".to_owned());
+
+        let form = Grant::Password.form(&config).unwrap();
+        assert!(form.contains(&("password", "PASSWORD".to_owned())));
+        assert!(matches!(
+            auth_error("invalid_grant".to_owned(), &config, &Grant::Password),
+            RouxError::Auth(_)
+        ));
+
+        config.otp = Some("123456".to_owned());
+
+        let form = Grant::Password.form(&config).unwrap();
+        assert!(form.contains(&("password", "PASSWORD:123456".to_owned())));
+        assert!(matches!(
+            auth_error("invalid_grant".to_owned(), &config, &Grant::Password),
+            RouxError::TwoFactor(_)
+        ));
+
+        config.spend_otp();
+        assert!(Grant::Password.renewal(&config).is_none());
+        assert!(Grant::Password.form(&config).is_none());
+        assert!(matches!(
+            auth_error("invalid_grant".to_owned(), &config, &Grant::Password),
+            RouxError::Auth(_)
+        ));
+    }
+
+    #[cfg(feature = "totp")]
+    #[test]
+    fn test_invalid_totp_secret() {
+        let mut config = Config::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET");
+        config.totp_secret = Some("not base32!".to_owned());
+        assert!(matches!(config.check_otp(), Err(RouxError::TwoFactor(_))));
+
+        config.totp_secret = Some("JBSWY3DPEHPK3PXP".to_owned());
+        assert!(config.check_otp().is_ok());
+    }
+}
@@ -1,5 +1,7 @@
 extern crate reqwest;
 
+use std::time::Duration;
+
 pub use reqwest::Error;
 
 #[cfg(not(feature = "blocking"))]
@@ -7,3 +9,15 @@ pub use ::reqwest::*;
 
 #[cfg(feature = "blocking")]
 pub use reqwest::blocking::*;
+
+/// Wait for `duration` without blocking the async runtime.
+#[cfg(not(feature = "blocking"))]
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+/// Wait for `duration`.
+#[cfg(feature = "blocking")]
+pub fn sleep(duration: Duration) {
+    std::thread::sleep(duration)
+}
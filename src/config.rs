@@ -1,22 +1,86 @@
+use std::sync::Arc;
+
+use crate::util::{url, RateLimiter, RetryPolicy, RouxError, TokenStore};
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub user_agent: String,
+    pub base_url: String,
+    pub oauth_url: String,
     pub client_id: String,
     pub client_secret: String,
     pub username: Option<String>,
     pub password: Option<String>,
     pub access_token: Option<String>,
+    pub device_id: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub refresh_token: Option<String>,
+    pub scopes: Vec<String>,
+    pub token_store: Option<Arc<dyn TokenStore>>,
+    pub rate_limiter: RateLimiter,
+    pub retry_policy: RetryPolicy,
+    pub upload_url: Option<String>,
+    pub otp: Option<String>,
+    /// Set once a fixed one-time code has been used, so the password alone is not sent again.
+    pub otp_spent: bool,
+    #[cfg(feature = "totp")]
+    pub totp_secret: Option<String>,
 }
 
 impl Config {
     pub fn new(user_agent: &str, client_id: &str, client_secret: &str) -> Config {
         Config {
             user_agent: user_agent.to_owned(),
+            base_url: url::BASE_URL.to_owned(),
+            oauth_url: url::OAUTH_URL.to_owned(),
             client_id: client_id.to_owned(),
             client_secret: client_secret.to_owned(),
             username: None,
             password: None,
             access_token: None,
+            device_id: None,
+            redirect_uri: None,
+            refresh_token: None,
+            scopes: Vec::new(),
+            token_store: None,
+            rate_limiter: RateLimiter::new(),
+            retry_policy: RetryPolicy::default(),
+            upload_url: None,
+            otp: None,
+            otp_spent: false,
+            #[cfg(feature = "totp")]
+            totp_secret: None,
+        }
+    }
+
+    /// The one-time code to send with a password login, if two-factor authentication is set up.
+    pub fn otp(&self) -> Option<String> {
+        #[cfg(feature = "totp")]
+        if let Some(secret) = &self.totp_secret {
+            return crate::util::totp::totp(secret);
+        }
+
+        self.otp.to_owned()
+    }
+
+    /// Fails if the TOTP secret is not valid base32, rather than logging in without a code.
+    pub fn check_otp(&self) -> Result<(), RouxError> {
+        #[cfg(feature = "totp")]
+        if let Some(secret) = &self.totp_secret {
+            if crate::util::totp::totp(secret).is_none() {
+                return Err(RouxError::TwoFactor(
+                    "the TOTP secret is not valid base32".to_owned(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forget a fixed one-time code after a login used it, Reddit does not take it twice.
+    pub fn spend_otp(&mut self) {
+        if self.otp.take().is_some() {
+            self.otp_spent = true;
         }
     }
 }
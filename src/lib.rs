@@ -27,6 +27,37 @@
 //!
 //! This will authticate you as the user given in the username function.
 //!
+//! ## Authorization Code Flow
+//! Web apps that act on behalf of other users send them to [`Reddit::authorize_url`], then
+//! exchange the `code` Reddit redirects back with for an access and refresh token.
+//! ```no_run
+//! use roux::Reddit;
+//! # #[cfg(not(feature = "blocking"))]
+//! # use tokio;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let reddit = Reddit::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET")
+//!     .redirect_uri("https://example.com/callback")
+//!     .scopes(&["identity", "read", "submit"]);
+//!
+//! let url = reddit.authorize_url("STATE").unwrap();
+//! // Send the user to `url`, then read `code` from the redirect.
+//!
+//! let me = reddit.authorize("CODE").await.unwrap();
+//! if me.has_scope("submit") {
+//!     me.submit_text("TEXT_TITLE", "TEXT_BODY", "SUBREDDIT").await;
+//! }
+//!
+//! // Keep the refresh token to log in again later without the user.
+//! let refresh_token = me.refresh_token().unwrap();
+//! let me = Reddit::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET")
+//!     .refresh_token(&refresh_token)
+//!     .login()
+//!     .await;
+//! # }
+//! ```
 //!
 //! ## Usage
 //! Using the OAuth client, you can:
@@ -71,22 +102,24 @@
 //! # }
 //! ```
 
-use serde::Deserialize;
-
-use reqwest::header;
-use reqwest::header::USER_AGENT;
+use std::sync::Arc;
 
 mod config;
 
 mod client;
 use client::Client;
 
+mod auth;
+use auth::Grant;
+
+mod session;
+use session::Session;
+
 mod models;
 pub use models::*;
 
 /// Utils for requests.
 pub mod util;
-use util::url;
 
 /// Client to use OAuth with Reddit.
 #[derive(Clone)]
@@ -95,13 +128,6 @@ pub struct Reddit {
     client: Client,
 }
 
-#[derive(Deserialize, Debug)]
-#[serde(untagged)]
-enum AuthResponse {
-    AuthData { access_token: String },
-    ErrorData { error: String },
-}
-
 impl Reddit {
     /// Creates a `Reddit` instance with user_agent, client_id, and client_secret.
     pub fn new(user_agent: &str, client_id: &str, client_secret: &str) -> Reddit {
@@ -123,77 +149,229 @@ impl Reddit {
         self
     }
 
+    /// Sets the base url for logging in and read only requests, `https://www.reddit.com` by
+    /// default. Together with [`Reddit::oauth_url`] this can point at a local server that
+    /// stands in for Reddit in tests.
+    pub fn base_url(mut self, base_url: &str) -> Reddit {
+        self.config.base_url = base_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Sets the base url for authenticated requests, `https://oauth.reddit.com` by default.
+    pub fn oauth_url(mut self, oauth_url: &str) -> Reddit {
+        self.config.oauth_url = oauth_url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Sets the one-time code for accounts with two-factor authentication. It is sent with
+    /// the password, so it has to be set shortly before [`Reddit::login`]. It is only used
+    /// once: the token is then renewed with a refresh token if there is one, and otherwise
+    /// not at all, so requests fail with
+    /// [`RouxError::Unauthorized`](util::RouxError::Unauthorized) once it expires. Use
+    /// `Reddit::totp_secret` (with the `totp` feature) for long running logins.
+    pub fn otp(mut self, code: &str) -> Reddit {
+        self.config.otp = Some(code.to_owned());
+        self
+    }
+
+    /// Sets the base32 secret for accounts with two-factor authentication, so a fresh one-time
+    /// code is generated for every password login, including token renewals. Logging in fails
+    /// with [`RouxError::TwoFactor`](util::RouxError::TwoFactor) if `secret` is not valid
+    /// base32.
+    #[cfg(feature = "totp")]
+    pub fn totp_secret(mut self, secret: &str) -> Reddit {
+        self.config.totp_secret = Some(secret.to_owned());
+        self
+    }
+
+    /// Sets the redirect uri registered for the app, used by the authorization code flow.
+    pub fn redirect_uri(mut self, redirect_uri: &str) -> Reddit {
+        self.config.redirect_uri = Some(redirect_uri.to_owned());
+        self
+    }
+
+    /// Sets the scopes to request in [`Reddit::authorize_url`], e.g. `["identity", "read"]`.
+    pub fn scopes(mut self, scopes: &[&str]) -> Reddit {
+        self.config.scopes = scopes.iter().map(|scope| scope.to_string()).collect();
+        self
+    }
+
+    /// Sets a refresh token from a previous authorization. If no username and password are
+    /// set, [`Reddit::login`] will use it to get a new access token.
+    pub fn refresh_token(mut self, refresh_token: &str) -> Reddit {
+        self.config.refresh_token = Some(refresh_token.to_owned());
+        self
+    }
+
+    /// Sets a store to restore tokens from and save them to, so logins survive restarts.
+    ///
+    /// Tokens are stored under the client id and the username, or the device id for
+    /// [`Reddit::app_only`], so set [`Reddit::username`] when logging in with a refresh token.
+    /// Without it the token is only saved once the username has been looked up, which needs
+    /// the `identity` scope.
+    pub fn token_store<S: util::TokenStore + 'static>(mut self, store: S) -> Reddit {
+        self.config.token_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Sets the device id used by the `installed_client` grant in [`Reddit::app_only`].
+    ///
+    /// Reddit asks for a unique 20-30 character string per device, or
+    /// `DO_NOT_TRACK_THIS_DEVICE`.
+    pub fn device_id(mut self, device_id: &str) -> Reddit {
+        self.config.device_id = Some(device_id.to_owned());
+        self
+    }
+
+    /// Sets how requests that fail with a server error or a network error are retried,
+    /// [`RetryPolicy::default`](util::RetryPolicy::default) if not set.
+    pub fn retry_policy(mut self, policy: util::RetryPolicy) -> Reddit {
+        self.config.retry_policy = policy;
+        self
+    }
+
+    /// Sets where media is uploaded to, instead of the storage Reddit names for each upload.
+    /// Like [`Reddit::base_url`], this can point at a local server that stands in for it in
+    /// tests.
+    pub fn upload_url(mut self, upload_url: &str) -> Reddit {
+        self.config.upload_url = Some(upload_url.trim_end_matches('/').to_owned());
+        self
+    }
+
     #[maybe_async::maybe_async]
-    async fn create_client(mut self) -> Result<Reddit, util::RouxError> {
-        let url = &url::build_url("api/v1/access_token")[..];
-        let form = [
-            ("grant_type", "password"),
-            (
-                "username",
-                &self
-                    .config
-                    .username
-                    .to_owned()
-                    .ok_or(util::RouxError::CredentialsNotSet)?,
-            ),
-            (
-                "password",
-                &self
-                    .config
-                    .password
-                    .to_owned()
-                    .ok_or(util::RouxError::CredentialsNotSet)?,
-            ),
-        ];
-
-        let request = self
-            .client
-            .post(url)
-            .header(USER_AGENT, &self.config.user_agent[..])
-            .basic_auth(&self.config.client_id, Some(&self.config.client_secret))
-            .form(&form);
-
-        let response = request.send().await?;
-
-        if response.status() == 200 {
-            let auth_data = response.json::<AuthResponse>().await?;
-
-            let access_token = match auth_data {
-                AuthResponse::AuthData { access_token } => access_token,
-                AuthResponse::ErrorData { error } => return Err(util::RouxError::Auth(error)),
-            };
-            let mut headers = header::HeaderMap::new();
-
-            headers.insert(
-                header::AUTHORIZATION,
-                header::HeaderValue::from_str(&format!("Bearer {}", access_token)).unwrap(),
-            );
-
-            headers.insert(
-                header::USER_AGENT,
-                header::HeaderValue::from_str(&self.config.user_agent[..]).unwrap(),
-            );
-
-            self.config.access_token = Some(access_token);
-            self.client = Client::builder().default_headers(headers).build().unwrap();
-
-            Ok(self)
-        } else {
-            Err(util::RouxError::Status(response))
-        }
+    async fn authenticate(mut self, mut grant: Grant) -> Result<Session, util::RouxError> {
+        // A new authorization code is always exchanged, whatever is in the store.
+        let restored = match grant {
+            Grant::AuthorizationCode(_) => None,
+            _ => auth::load_token(&self.config, &grant)
+                .map_err(|e| util::RouxError::TokenStore(Arc::new(e)))?,
+        };
+
+        let token = match restored {
+            Some(token) if !token.expires_soon() => token,
+            restored => {
+                if let Some(refresh_token) = restored.and_then(|token| token.refresh_token) {
+                    self.config.refresh_token = Some(refresh_token);
+                    grant = Grant::RefreshToken;
+                }
+
+                let token = auth::request_token(&self.client, &self.config, &grant).await?;
+                if matches!(grant, Grant::Password) {
+                    self.config.spend_otp();
+                }
+                token.apply(&mut self.config);
+                auth::save_token(&self.config, &grant, &token)
+                    .map_err(|e| util::RouxError::TokenStore(Arc::new(e)))?;
+                token
+            }
+        };
+
+        let client = auth::session_client(&self.config);
+
+        Ok(Session::authenticated(
+            &self.config,
+            &client,
+            Some(grant),
+            token,
+        ))
+    }
+
+    #[maybe_async::maybe_async]
+    async fn create_client(self) -> Result<Session, util::RouxError> {
+        let grant = Grant::from_config(&self.config).ok_or(util::RouxError::CredentialsNotSet)?;
+        self.authenticate(grant).await
+    }
+
+    /// Build the url to send a user to so they can authorize the app.
+    ///
+    /// `state` is returned unchanged in the redirect and should be checked against the
+    /// value sent here. A permanent authorization is requested so a refresh token is issued.
+    /// Returns `None` if [`Reddit::redirect_uri`] has not been set.
+    pub fn authorize_url(&self, state: &str) -> Option<String> {
+        let redirect_uri = self.config.redirect_uri.as_deref()?;
+        let scope = self.config.scopes.join(" ");
+
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/api/v1/authorize", self.config.base_url),
+            &[
+                ("client_id", &self.config.client_id[..]),
+                ("response_type", "code"),
+                ("state", state),
+                ("redirect_uri", redirect_uri),
+                ("duration", "permanent"),
+                ("scope", &scope[..]),
+            ],
+        )
+        .expect("Error building authorize url");
+
+        Some(url.to_string())
+    }
+
+    /// Exchange the `code` from the authorization redirect for an access and refresh token.
+    #[maybe_async::maybe_async]
+    pub async fn authorize(self, code: &str) -> Result<me::Me, util::RouxError> {
+        let session = self
+            .authenticate(Grant::AuthorizationCode(code.to_owned()))
+            .await?;
+        me::Me::with_identity(session).await
     }
 
     /// Login as a user.
+    ///
+    /// The returned `Me` renews its access token before it expires, and again if Reddit
+    /// rejects it, using the refresh token if there is one or the password otherwise.
     #[maybe_async::maybe_async]
     pub async fn login(self) -> Result<me::Me, util::RouxError> {
-        let reddit = self.create_client().await?;
-        Ok(me::Me::new(&reddit.config, &reddit.client))
+        let session = self.create_client().await?;
+        me::Me::with_identity(session).await
     }
 
     /// Create a new authenticated `Subreddit` instance.
     #[maybe_async::maybe_async]
     pub async fn subreddit(self, name: &str) -> Result<models::Subreddit, util::RouxError> {
-        let reddit = self.create_client().await?;
-        Ok(models::Subreddit::new_oauth(name, &reddit.client))
+        let oauth_url = self.config.oauth_url.to_owned();
+        let session = self.create_client().await?;
+        Ok(models::Subreddit::from_session(name, session).base_url(&oauth_url))
+    }
+
+    /// Authenticate as the application itself rather than as a user.
+    ///
+    /// This uses the `client_credentials` grant, or the `installed_client` grant if
+    /// [`Reddit::device_id`] has been set. The returned handle can only read public data, but
+    /// its requests go through `oauth.reddit.com` and get the authenticated rate limits.
+    #[maybe_async::maybe_async]
+    pub async fn app_only(self) -> Result<models::AppOnly, util::RouxError> {
+        let grant = if self.config.device_id.is_some() {
+            Grant::InstalledClient
+        } else {
+            Grant::ClientCredentials
+        };
+
+        let session = self.authenticate(grant).await?;
+        Ok(models::AppOnly::from_session(session))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Reddit;
+
+    #[test]
+    fn test_authorize_url() {
+        let reddit = Reddit::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET");
+        assert!(reddit.authorize_url("state").is_none());
+
+        let url = reddit
+            .redirect_uri("http://localhost:8080/callback")
+            .scopes(&["identity", "read"])
+            .authorize_url("some state")
+            .unwrap();
+
+        assert_eq!(
+            url,
+            "https://www.reddit.com/api/v1/authorize?client_id=CLIENT_ID&response_type=code\
+             &state=some+state&redirect_uri=http%3A%2F%2Flocalhost%3A8080%2Fcallback\
+             &duration=permanent&scope=identity+read"
+        );
     }
 }
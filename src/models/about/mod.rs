@@ -1,11 +1,11 @@
 //! # User Overview Responses
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::models::response::BasicThing;
 use crate::models::subreddit::response::SubredditData;
 
 /// AboutData
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AboutData {
     /// Is employee
     pub is_employee: Option<bool>,
@@ -0,0 +1,108 @@
+//! # App Only
+//! A read-only module for application-only OAuth.
+//!
+//! # Usage
+//! ```no_run
+//! use roux::Reddit;
+//! # #[cfg(not(feature = "blocking"))]
+//! # use tokio;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let app = Reddit::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET")
+//!     .app_only()
+//!     .await
+//!     .unwrap();
+//!
+//! // Get hot posts with limit = 25.
+//! let hot = app.subreddit("rust").hot(25, None).await;
+//!
+//! // Get a user's comments.
+//! let comments = app.user("beneater").comments(None).await;
+//!
+//! // Search subreddits.
+//! let subreddits = app.search_subreddits("rust", Some(5), None).await;
+//! # }
+//! ```
+
+use crate::auth::{Grant, Token};
+use crate::client::Client;
+use crate::config::Config;
+use crate::models::subreddit::response::SubredditsData;
+use crate::models::{Subreddit, Subreddits, User};
+use crate::session::Session;
+use crate::util::{FeedOption, RateLimit, RouxError};
+
+/// AppOnly
+#[derive(Debug, Clone)]
+pub struct AppOnly {
+    config: Config,
+    session: Session,
+}
+
+impl AppOnly {
+    /// Create a new `AppOnly`
+    ///
+    /// If `config` has an access token it is sent with every request, and renewed with the
+    /// `client_credentials` grant (or `installed_client` if `config` has a device id) if
+    /// Reddit rejects it.
+    pub fn new(config: &Config, client: &Client) -> AppOnly {
+        let session = match &config.access_token {
+            Some(access_token) => {
+                let grant = if config.device_id.is_some() {
+                    Grant::InstalledClient
+                } else {
+                    Grant::ClientCredentials
+                };
+                Session::authenticated(config, client, Some(grant), Token::new(access_token))
+            }
+            None => Session::new(client),
+        };
+
+        AppOnly {
+            config: config.to_owned(),
+            session,
+        }
+    }
+
+    /// Create a new `AppOnly` from an authenticated session.
+    pub(crate) fn from_session(session: Session) -> AppOnly {
+        AppOnly {
+            config: session.config().expect("Session is not authenticated"),
+            session,
+        }
+    }
+
+    /// Create an authenticated `Subreddit` instance. It shares this handle's token.
+    pub fn subreddit(&self, name: &str) -> Subreddit {
+        Subreddit::from_session(name, self.session.to_owned()).base_url(&self.config.oauth_url)
+    }
+
+    /// Create an authenticated `User` instance. It shares this handle's token.
+    pub fn user(&self, name: &str) -> User {
+        User::from_session(name, self.session.to_owned()).base_url(&self.config.oauth_url)
+    }
+
+    /// The current access token. It changes when the token is renewed, so read it again
+    /// rather than keeping it.
+    pub fn access_token(&self) -> Option<String> {
+        self.session.access_token()
+    }
+
+    /// The request budget Reddit last reported. Handles created from this one share it.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.session.rate_limit()
+    }
+
+    /// Search subreddits
+    #[maybe_async::maybe_async]
+    pub async fn search_subreddits(
+        &self,
+        name: &str,
+        limit: Option<u32>,
+        options: Option<FeedOption>,
+    ) -> Result<SubredditsData, RouxError> {
+        Subreddits::search_with(&self.session, &self.config.oauth_url, name, limit, options).await
+    }
+}
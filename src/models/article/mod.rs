@@ -0,0 +1,35 @@
+//! # Article Responses
+use serde::Deserialize;
+
+use crate::models::comment::Comments;
+use crate::models::submission::{SubmissionData, Submissions};
+
+/// A post with its comments.
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "(Submissions, Comments)")]
+pub struct Article {
+    /// The post
+    pub submission: SubmissionData,
+    /// The comments, as far as Reddit inlined them
+    pub comments: Comments,
+}
+
+/// Reddit answers with two listings, the first holding only the post.
+impl TryFrom<(Submissions, Comments)> for Article {
+    type Error = String;
+
+    fn try_from((submissions, comments): (Submissions, Comments)) -> Result<Article, String> {
+        let submission = submissions
+            .data
+            .children
+            .into_iter()
+            .next()
+            .ok_or("article has no submission")?
+            .data;
+
+        Ok(Article {
+            submission,
+            comments,
+        })
+    }
+}
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::models::{reply::MaybeReplies, response::BasicListing};
 
+pub mod tree;
+
 /// SubredditCommentsData
 /// Everything is an option to deal with both `latest_comments` and `article_comments`
 #[derive(Serialize, Debug, Deserialize)]
@@ -105,6 +107,16 @@ pub struct CommentData {
     pub ups: Option<i32>,
     /// Replies
     pub replies: Option<MaybeReplies>,
+    /// Inbox item type, e.g. `comment_reply` or `username_mention` (inbox only)
+    pub r#type: Option<String>,
+    /// Subject (inbox only)
+    pub subject: Option<String>,
+    /// Context (inbox only)
+    pub context: Option<String>,
+    /// Was comment (inbox only)
+    pub was_comment: Option<bool>,
+    /// New (inbox only)
+    pub new: Option<bool>,
 }
 
 /// SubredditComments
@@ -0,0 +1,304 @@
+//! # Comment trees
+//! Reddit only inlines part of a thread. The comments it leaves out are replaced by `more`
+//! stubs listing their ids, or by "continue this thread" links once the thread gets deep.
+//! [`Subreddit::comment_tree`](crate::Subreddit::comment_tree) expands all of them and
+//! returns the whole thread as a [`CommentTree`].
+//!
+//! ```no_run
+//! use roux::Subreddit;
+//! # #[cfg(not(feature = "blocking"))]
+//! # use tokio;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let subreddit = Subreddit::new("rust");
+//! let tree = subreddit.comment_tree("15bfi0").await.unwrap();
+//!
+//! for node in tree.depth_first() {
+//!     let indent = "  ".repeat(node.depth());
+//!     println!("{}{}", indent, node.comment().body.as_deref().unwrap_or_default());
+//! }
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::models::comment::CommentData;
+use crate::models::reply::MaybeReplies;
+use crate::models::thing::{MoreData, Thing};
+use crate::util::Fullname;
+
+/// How many ids `/api/morechildren` takes at once.
+pub(crate) const MORE_CHILDREN_LEN: usize = 100;
+
+/// A thread of comments, owned and linked both ways.
+#[derive(Debug, Default)]
+pub struct CommentTree {
+    nodes: Vec<Node>,
+    roots: Vec<usize>,
+    names: HashMap<String, usize>,
+}
+
+#[derive(Debug)]
+struct Node {
+    comment: CommentData,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    depth: usize,
+}
+
+/// Comments that were left out of a listing.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct More {
+    /// The fullname of the comment or post they reply to.
+    pub(crate) parent_id: String,
+    /// Their ids, or none for a "continue this thread" link.
+    pub(crate) children: Vec<String>,
+}
+
+impl From<MoreData> for More {
+    fn from(data: MoreData) -> More {
+        More {
+            parent_id: data.parent_id,
+            children: data.children,
+        }
+    }
+}
+
+impl CommentTree {
+    pub(crate) fn new() -> CommentTree {
+        CommentTree::default()
+    }
+
+    /// Adds `things` and the replies inlined in them, and returns the comments that were
+    /// left out. A comment's parent must have been added before it, which is the order
+    /// Reddit lists comments in.
+    pub(crate) fn insert(&mut self, things: Vec<Thing>) -> Vec<More> {
+        let mut more = Vec::new();
+        for thing in things {
+            self.insert_thing(thing, &mut more);
+        }
+        more
+    }
+
+    fn insert_thing(&mut self, thing: Thing, more: &mut Vec<More>) {
+        let mut comment = match thing {
+            Thing::Comment(comment) => comment,
+            Thing::More(stub) => return more.push(stub.into()),
+            _ => return,
+        };
+
+        let replies = comment.replies.take();
+        let name = comment
+            .name
+            .to_owned()
+            .or_else(|| comment.id.as_ref().map(|id| format!("t1_{}", id)));
+
+        // A sub-thread fetched for a "continue this thread" link starts with a comment that
+        // is already in the tree, only its replies are new.
+        if !name
+            .as_ref()
+            .is_some_and(|name| self.names.contains_key(name))
+        {
+            let parent = comment
+                .parent_id
+                .as_ref()
+                .and_then(|parent_id| self.names.get(parent_id))
+                .copied();
+            let index = self.nodes.len();
+
+            self.nodes.push(Node {
+                comment,
+                parent,
+                children: Vec::new(),
+                depth: parent.map_or(0, |parent| self.nodes[parent].depth + 1),
+            });
+
+            match parent {
+                Some(parent) => self.nodes[parent].children.push(index),
+                None => self.roots.push(index),
+            }
+
+            if let Some(name) = name {
+                self.names.insert(name, index);
+            }
+        }
+
+        if let Some(MaybeReplies::Reply(replies)) = replies {
+            for reply in replies.data.children {
+                self.insert_thing(reply, more);
+            }
+        }
+    }
+
+    /// The number of comments.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether there are no comments.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The top level comments, in the order Reddit sorted them.
+    pub fn roots(&self) -> impl Iterator<Item = CommentNode<'_>> + '_ {
+        self.roots.iter().map(move |&index| self.node(index))
+    }
+
+    /// The comment with `fullname`, if it is in the tree.
+    pub fn get(&self, fullname: &Fullname) -> Option<CommentNode<'_>> {
+        self.names
+            .get(&fullname.to_string())
+            .map(|&index| self.node(index))
+    }
+
+    /// Every comment, each followed by its replies, like the thread is shown on Reddit.
+    pub fn depth_first(&self) -> impl Iterator<Item = CommentNode<'_>> + '_ {
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+
+        std::iter::from_fn(move || {
+            let index = stack.pop()?;
+            stack.extend(self.nodes[index].children.iter().rev());
+            Some(self.node(index))
+        })
+    }
+
+    /// Every comment, level by level: all top level comments first, then all their replies,
+    /// and so on.
+    pub fn breadth_first(&self) -> impl Iterator<Item = CommentNode<'_>> + '_ {
+        let mut queue: VecDeque<usize> = self.roots.iter().copied().collect();
+
+        std::iter::from_fn(move || {
+            let index = queue.pop_front()?;
+            queue.extend(&self.nodes[index].children);
+            Some(self.node(index))
+        })
+    }
+
+    fn node(&self, index: usize) -> CommentNode<'_> {
+        CommentNode { tree: self, index }
+    }
+}
+
+/// A comment in a [`CommentTree`].
+#[derive(Clone, Copy)]
+pub struct CommentNode<'a> {
+    tree: &'a CommentTree,
+    index: usize,
+}
+
+impl<'a> CommentNode<'a> {
+    /// The comment. Its `replies` are always `None`, use [`CommentNode::children`] instead.
+    pub fn comment(&self) -> &'a CommentData {
+        &self.tree.nodes[self.index].comment
+    }
+
+    /// The comment this one replies to, or `None` for a top level comment.
+    pub fn parent(&self) -> Option<CommentNode<'a>> {
+        self.tree.nodes[self.index]
+            .parent
+            .map(|index| self.tree.node(index))
+    }
+
+    /// The replies to this comment.
+    pub fn children(&self) -> impl Iterator<Item = CommentNode<'a>> + 'a {
+        let tree = self.tree;
+        tree.nodes[self.index]
+            .children
+            .iter()
+            .map(move |&index| tree.node(index))
+    }
+
+    /// How deep the comment is, `0` for a top level comment.
+    pub fn depth(&self) -> usize {
+        self.tree.nodes[self.index].depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommentTree, More};
+    use crate::models::thing::Things;
+
+    fn tree() -> (CommentTree, Vec<More>) {
+        let comments: Things = serde_json::from_str(
+            r#"{"kind": "Listing", "data": {"after": null, "before": null, "children": [
+                {"kind": "t1", "data": {"id": "a", "name": "t1_a", "parent_id": "t3_p",
+                    "replies": {"kind": "Listing", "data": {"after": null, "before": null,
+                        "children": [
+                            {"kind": "t1", "data": {"id": "c", "name": "t1_c",
+                                "parent_id": "t1_a", "replies": ""}},
+                            {"kind": "more", "data": {"count": 2, "name": "t1_d", "id": "d",
+                                "parent_id": "t1_a", "depth": 1, "children": ["d", "e"]}}
+                        ]}}}},
+                {"kind": "t1", "data": {"id": "b", "name": "t1_b", "parent_id": "t3_p",
+                    "replies": ""}},
+                {"kind": "more", "data": {"count": 0, "name": "t1__", "id": "_",
+                    "parent_id": "t1_b", "depth": 1, "children": []}}
+            ]}}"#,
+        )
+        .unwrap();
+
+        let mut tree = CommentTree::new();
+        let more = tree.insert(comments.data.children);
+        (tree, more)
+    }
+
+    fn ids<'a>(nodes: impl Iterator<Item = super::CommentNode<'a>>) -> Vec<&'a str> {
+        nodes
+            .map(|node| node.comment().id.as_deref().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_insert() {
+        let (mut tree, more) = tree();
+        assert_eq!(tree.len(), 3);
+        assert_eq!(
+            more,
+            [
+                More {
+                    parent_id: "t1_a".to_owned(),
+                    children: vec!["d".to_owned(), "e".to_owned()],
+                },
+                More {
+                    parent_id: "t1_b".to_owned(),
+                    children: Vec::new(),
+                },
+            ]
+        );
+
+        // Expanded comments are attached to their parents, and comments already in the
+        // tree are not added twice.
+        let expanded: Things = serde_json::from_str(
+            r#"{"kind": "Listing", "data": {"after": null, "before": null, "children": [
+                {"kind": "t1", "data": {"id": "d", "name": "t1_d", "parent_id": "t1_a"}},
+                {"kind": "t1", "data": {"id": "f", "name": "t1_f", "parent_id": "t1_d"}},
+                {"kind": "t1", "data": {"id": "c", "name": "t1_c", "parent_id": "t1_a"}}
+            ]}}"#,
+        )
+        .unwrap();
+        assert!(tree.insert(expanded.data.children).is_empty());
+        assert_eq!(tree.len(), 5);
+
+        let f = tree.get(&"t1_f".parse().unwrap()).unwrap();
+        assert_eq!(f.depth(), 2);
+        assert_eq!(ids(f.parent().into_iter()), ["d"]);
+        assert!(tree.get(&"t1_x".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_iterators() {
+        let (tree, _) = tree();
+        assert_eq!(ids(tree.roots()), ["a", "b"]);
+        assert_eq!(ids(tree.depth_first()), ["a", "c", "b"]);
+        assert_eq!(ids(tree.breadth_first()), ["a", "b", "c"]);
+
+        let a = tree.roots().next().unwrap();
+        assert_eq!(ids(a.children()), ["c"]);
+        assert!(a.parent().is_none());
+        assert!(a.comment().replies.is_none());
+    }
+}
@@ -1,10 +1,10 @@
 //! # Inbox Responses
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::models::response::BasicListing;
+use crate::models::thing::Things;
 
-/// InboxItem
-#[derive(Debug, Deserialize)]
+/// A private message (`t4`)
+#[derive(Debug, Serialize, Deserialize)]
 pub struct InboxData {
     /// ID
     pub id: String,
@@ -38,5 +38,5 @@ pub struct InboxData {
     pub context: String,
 }
 
-/// Inbox
-pub type Inbox = BasicListing<InboxData>;
+/// Inbox. Replies and mentions are comments, everything else is a message.
+pub type Inbox = Things;
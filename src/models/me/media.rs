@@ -0,0 +1,306 @@
+//! # Media
+//! Image and video posts link to media uploaded to Reddit's storage first. Reddit hands out
+//! a lease for each file: where to upload it, and the form fields the upload needs.
+//! [`Me::upload_media`](crate::Me::upload_media) takes the lease and uploads the file;
+//! [`Me::submit_image`](crate::Me::submit_image) and
+//! [`Me::submit_video`](crate::Me::submit_video) also post it.
+//!
+//! ```no_run
+//! use roux::Reddit;
+//! use roux::me::media::Media;
+//! # #[cfg(not(feature = "blocking"))]
+//! # use tokio;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! # let me = Reddit::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET").login().await.unwrap();
+//! let image = Media::from_path("ferris.png").unwrap();
+//! let submitted = me.submit_image("Ferris", image, "rust").await.unwrap();
+//! println!("Posted {}", submitted.url);
+//! # }
+//! ```
+
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::util::RouxError;
+
+/// The file types Reddit takes, by extension.
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("mp4", "video/mp4"),
+    ("mov", "video/quicktime"),
+];
+
+/// Whether media is posted as an image or a video.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MediaKind {
+    /// PNG, JPEG or GIF
+    Image,
+    /// MP4 or QuickTime
+    Video,
+}
+
+/// A file to upload.
+#[derive(Clone, PartialEq)]
+pub struct Media {
+    file_name: String,
+    mime_type: &'static str,
+    bytes: Vec<u8>,
+}
+
+impl Media {
+    /// Read the file at `path`. Its type is told by its extension.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Media, RouxError> {
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| RouxError::InvalidMedia(format!("{} is not a file", path.display())))?;
+        let bytes = fs::read(path)
+            .map_err(|e| RouxError::InvalidMedia(format!("{}: {}", path.display(), e)))?;
+
+        Media::from_bytes(file_name, bytes)
+    }
+
+    /// Use `bytes` as the contents of a file named `file_name`. Its type is told by the
+    /// extension, e.g. `.png`.
+    pub fn from_bytes(file_name: &str, bytes: Vec<u8>) -> Result<Media, RouxError> {
+        let extension = file_name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase())
+            .unwrap_or_default();
+        let mime_type = MIME_TYPES
+            .iter()
+            .find(|(ext, _)| *ext == extension)
+            .map(|(_, mime_type)| *mime_type)
+            .ok_or_else(|| {
+                RouxError::InvalidMedia(format!(
+                    "{} is not a PNG, JPEG, GIF, MP4 or MOV file",
+                    file_name
+                ))
+            })?;
+
+        if !has_signature(mime_type, &bytes) {
+            return Err(RouxError::InvalidMedia(format!(
+                "{} does not contain {}",
+                file_name, mime_type
+            )));
+        }
+
+        Ok(Media {
+            file_name: file_name.to_owned(),
+            mime_type,
+            bytes,
+        })
+    }
+
+    /// The file name.
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// The MIME type, e.g. `image/png`.
+    pub fn mime_type(&self) -> &str {
+        self.mime_type
+    }
+
+    /// Whether the file is an image or a video.
+    pub fn kind(&self) -> MediaKind {
+        if self.mime_type.starts_with("video/") {
+            MediaKind::Video
+        } else {
+            MediaKind::Image
+        }
+    }
+
+    /// The contents.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl std::fmt::Debug for Media {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Media")
+            .field("file_name", &self.file_name)
+            .field("mime_type", &self.mime_type)
+            .field("len", &self.bytes.len())
+            .finish()
+    }
+}
+
+/// Whether `bytes` start the way a file of `mime_type` does.
+fn has_signature(mime_type: &str, bytes: &[u8]) -> bool {
+    match mime_type {
+        "image/png" => bytes.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "image/jpeg" => bytes.starts_with(b"\xff\xd8\xff"),
+        "image/gif" => bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a"),
+        // Both are ISO base media files, made of boxes with their type at offset 4.
+        _ => bytes.get(4..8).is_some_and(|box_type| {
+            [&b"ftyp"[..], b"moov", b"mdat", b"wide", b"free"].contains(&box_type)
+        }),
+    }
+}
+
+/// A file uploaded to Reddit's storage, ready to be posted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadedMedia {
+    /// The id Reddit gave the file
+    pub asset_id: String,
+    /// Where the file was uploaded to
+    pub url: String,
+    /// Whether the file is an image or a video
+    pub kind: MediaKind,
+}
+
+/// The answer to `api/media/asset`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct AssetLease {
+    pub(crate) args: LeaseArgs,
+    pub(crate) asset: Asset,
+}
+
+/// Where to upload a file, and the form fields to upload it with.
+#[derive(Debug, Deserialize)]
+pub(crate) struct LeaseArgs {
+    pub(crate) action: String,
+    pub(crate) fields: Vec<LeaseField>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LeaseField {
+    pub(crate) name: String,
+    pub(crate) value: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct Asset {
+    pub(crate) asset_id: String,
+}
+
+impl AssetLease {
+    /// The url to upload to: `target` if set, otherwise the storage the lease names.
+    pub(crate) fn upload_url(&self, target: Option<&str>) -> String {
+        match target {
+            Some(target) => target.trim_end_matches('/').to_owned(),
+            // Reddit leaves out the scheme.
+            None if self.args.action.starts_with("//") => format!("https:{}", self.args.action),
+            None => self.args.action.to_owned(),
+        }
+    }
+
+    /// The key the file is stored under.
+    pub(crate) fn key(&self) -> Option<&str> {
+        self.args
+            .fields
+            .iter()
+            .find(|field| field.name == "key")
+            .map(|field| field.value.as_str())
+    }
+
+    /// The `multipart/form-data` upload of `media`: the content type and the body.
+    pub(crate) fn multipart(&self, media: &Media) -> (String, Vec<u8>) {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos());
+        let boundary = format!("roux-{:x}", nanos);
+
+        let mut body = Vec::new();
+        for field in &self.args.fields {
+            body.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                    boundary, field.name, field.value
+                )
+                .as_bytes(),
+            );
+        }
+
+        // The file has to come last, storage ignores fields after it.
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                boundary,
+                media.file_name.replace('"', ""),
+                media.mime_type
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&media.bytes);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        (format!("multipart/form-data; boundary={}", boundary), body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AssetLease, Media, MediaKind};
+    use crate::util::RouxError;
+
+    #[test]
+    fn test_from_bytes() {
+        let image = Media::from_bytes("ferris.PNG", b"\x89PNG\r\n\x1a\n...".to_vec()).unwrap();
+        assert_eq!(image.mime_type(), "image/png");
+        assert_eq!(image.kind(), MediaKind::Image);
+
+        let video = Media::from_bytes("ferris.mp4", b"\0\0\0\x18ftypmp42".to_vec()).unwrap();
+        assert_eq!(video.mime_type(), "video/mp4");
+        assert_eq!(video.kind(), MediaKind::Video);
+
+        for (file_name, bytes) in [
+            ("ferris.txt", &b"hello"[..]),
+            ("ferris", &b"\x89PNG\r\n\x1a\n"[..]),
+            ("ferris.png", &b"\xff\xd8\xff\xe0"[..]),
+            ("ferris.mov", &b"\0\0"[..]),
+        ] {
+            assert!(matches!(
+                Media::from_bytes(file_name, bytes.to_vec()),
+                Err(RouxError::InvalidMedia(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_lease() {
+        let lease: AssetLease = serde_json::from_str(
+            r#"{"args": {"action": "//reddit-uploaded-media.s3-accelerate.amazonaws.com",
+                "fields": [{"name": "key", "value": "rte_images/abc"},
+                    {"name": "acl", "value": "private"}]},
+                "asset": {"asset_id": "abc", "processing_state": "incomplete",
+                    "websocket_url": "wss://example.com/abc"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(lease.key(), Some("rte_images/abc"));
+        assert_eq!(
+            lease.upload_url(None),
+            "https://reddit-uploaded-media.s3-accelerate.amazonaws.com"
+        );
+        assert_eq!(
+            lease.upload_url(Some("http://localhost:1/")),
+            "http://localhost:1"
+        );
+
+        let image = Media::from_bytes("a.gif", b"GIF89a".to_vec()).unwrap();
+        let (content_type, body) = lease.multipart(&image);
+        let boundary = content_type.split_once("boundary=").unwrap().1;
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.starts_with(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"key\"\r\n\r\nrte_images/abc\r\n",
+            boundary
+        )));
+        assert!(body.ends_with(&format!(
+            "filename=\"a.gif\"\r\nContent-Type: image/gif\r\n\r\nGIF89a\r\n--{}--\r\n",
+            boundary
+        )));
+    }
+}
@@ -1,55 +1,157 @@
 //! # Me
 //! Me module.
 
+pub mod media;
 pub mod response;
+pub mod submit;
 
 extern crate reqwest;
 extern crate serde_json;
+use std::collections::HashSet;
+use std::fmt;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 
-use crate::client::{Client, Response};
+use crate::auth::{Grant, Token};
+use crate::client::{self, Client, Response};
 use crate::config::Config;
-use crate::models::me::response::MeData;
-use crate::models::{Friend, Inbox, Saved};
-use crate::util::{url, FeedOption, RouxError};
+use crate::models::comment::CommentData;
+use crate::models::me::media::{AssetLease, Media, MediaKind, UploadedMedia};
+use crate::models::me::response::{MeData, SubmitData};
+use crate::models::me::submit::{GalleryBuilder, GallerySubmitted, SubmissionBuilder};
+use crate::models::response::{ApiResponse, ThingsData};
+use crate::models::{Article, Friend, Inbox, Saved, Thing};
+use crate::session::Session;
+use crate::util::fullname;
+use crate::util::stream::Poller;
+use crate::util::{
+    url, ArticleOption, CommentSort, FeedOption, Fullname, InboxOptions, ListingItem, LiveStream,
+    RateLimit, RouxError, StreamOptions,
+};
 use crate::Submissions;
 
 /// Me
 #[derive(Debug, Clone)]
 pub struct Me {
-    /// Config
-    pub config: Config,
-    /// Client
-    pub client: Client,
+    config: Config,
+    session: Session,
 }
 
 impl Me {
     /// Create a new `me`
+    ///
+    /// If `config` has an access token it is sent with every request, and renewed if Reddit
+    /// rejects it and `config` has a refresh token or a username and password.
     pub fn new(config: &Config, client: &Client) -> Me {
+        let session = match &config.access_token {
+            Some(access_token) => Session::authenticated(
+                config,
+                client,
+                Grant::from_config(config),
+                Token::new(access_token),
+            ),
+            None => Session::new(client),
+        };
+
         Me {
             config: config.to_owned(),
-            client: client.to_owned(),
+            session,
         }
     }
 
+    /// Create a new `me` from an authenticated session, looking up the username if it is not
+    /// already known and the `identity` scope was granted.
     #[maybe_async::maybe_async]
-    async fn get(&self, url: &str) -> Result<Response, RouxError> {
-        let get_url = url::build_oauth(url);
-
-        match self.client.get(&get_url[..]).send().await {
-            Ok(response) => Ok(response),
-            Err(e) => Err(e.into()),
+    pub(crate) async fn with_identity(session: Session) -> Result<Me, RouxError> {
+        let mut me = Me {
+            config: session.config().expect("Session is not authenticated"),
+            session,
+        };
+
+        if me.config.username.is_none() && me.has_scope("identity") {
+            let username = me.me().await?.name;
+            me.session.identify(&username)?;
+            me.config.username = Some(username);
         }
+
+        Ok(me)
+    }
+
+    /// The scopes granted to this session. Password logins are granted `*` (every scope).
+    pub fn scopes(&self) -> &[String] {
+        &self.config.scopes
+    }
+
+    /// Check if a scope was granted before calling an endpoint that needs it.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.config.scopes.iter().any(|s| s == "*" || s == scope)
+    }
+
+    /// The current access token. It changes when the token is renewed, so read it again
+    /// rather than keeping it.
+    pub fn access_token(&self) -> Option<String> {
+        self.session.access_token()
+    }
+
+    /// The current refresh token, to log in again later with
+    /// [`Reddit::refresh_token`](crate::Reddit::refresh_token).
+    pub fn refresh_token(&self) -> Option<String> {
+        self.session.refresh_token()
+    }
+
+    /// The request budget Reddit last reported. Every handle created from the same `Reddit`
+    /// shares this budget.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.session.rate_limit()
+    }
+
+    #[maybe_async::maybe_async]
+    async fn get(&self, url: &str) -> Result<Response, RouxError> {
+        let get_url = url::build(&self.config.oauth_url, url);
+        self.session.get(&get_url).await
     }
 
     #[maybe_async::maybe_async]
     async fn post<T: Serialize>(&self, url: &str, form: T) -> Result<Response, RouxError> {
-        let post_url = url::build_oauth(url).to_owned();
+        let post_url = url::build(&self.config.oauth_url, url);
+        self.session.post(&post_url, &form).await
+    }
+
+    /// Post a form with `api_type=json` and return the response's `json.data`, or the errors
+    /// Reddit reported.
+    #[maybe_async::maybe_async]
+    async fn post_api<T: Serialize, D: DeserializeOwned>(
+        &self,
+        url: &str,
+        form: T,
+    ) -> Result<Option<D>, RouxError> {
+        self.post(url, form)
+            .await?
+            .json::<ApiResponse<D>>()
+            .await?
+            .into_result()
+            .map_err(RouxError::Api)
+    }
+
+    /// Post a form to an endpoint that answers with `{}` when it succeeds, and fail with the
+    /// errors Reddit reported, if it reported any.
+    #[maybe_async::maybe_async]
+    async fn post_action<T: Serialize>(&self, url: &str, form: T) -> Result<(), RouxError> {
+        let body = self.post(url, form).await?.text().await?;
+        if body.trim().is_empty() {
+            return Ok(());
+        }
 
-        match self.client.post(&post_url[..]).form(&form).send().await {
-            Ok(response) => Ok(response),
-            Err(e) => Err(e.into()),
+        let body = serde_json::from_str::<serde_json::Value>(&body)?;
+        if body.get("json").is_some() {
+            serde_json::from_value::<ApiResponse<serde_json::Value>>(body)?
+                .into_result()
+                .map_err(RouxError::Api)?;
         }
+
+        Ok(())
     }
 
     /// Get me
@@ -61,6 +163,195 @@ impl Me {
         }
     }
 
+    /// Submit a post and return what Reddit created.
+    ///
+    /// Reddit creates image and video posts only once it has processed the media, and
+    /// announces them over a websocket. roux looks for them among the user's posts instead,
+    /// for up to a minute, and fails with [`RouxError::MediaProcessing`] if they do not show up.
+    #[maybe_async::maybe_async]
+    pub async fn submit(&self, submission: SubmissionBuilder) -> Result<SubmitData, RouxError> {
+        if submission.is_media() {
+            return self.submit_media(submission).await;
+        }
+
+        self.post_api::<_, SubmitData>("api/submit", submission.form())
+            .await?
+            .ok_or_else(|| missing_data("api/submit"))
+    }
+
+    #[maybe_async::maybe_async]
+    async fn submit_media(&self, submission: SubmissionBuilder) -> Result<SubmitData, RouxError> {
+        let sr = submission.sr().to_owned();
+        let title = submission.title().to_owned();
+        let username = self.username().await?;
+
+        // Posts from before, so an older post with the same title is not taken for this one.
+        let before: HashSet<String> = self
+            .latest_submissions(&username)
+            .await?
+            .data
+            .children
+            .into_iter()
+            .map(|post| post.data.name)
+            .collect();
+
+        self.post_api::<_, serde_json::Value>("api/submit", submission.form())
+            .await?;
+
+        for attempt in 0..MEDIA_POLLS {
+            if attempt > 0 {
+                client::sleep(MEDIA_POLL_INTERVAL).await;
+            }
+
+            let created = self
+                .latest_submissions(&username)
+                .await?
+                .data
+                .children
+                .into_iter()
+                .map(|post| post.data)
+                .find(|post| {
+                    !before.contains(&post.name)
+                        && post.title == title
+                        && post.subreddit.eq_ignore_ascii_case(&sr)
+                });
+
+            if let Some(post) = created {
+                return Ok(SubmitData {
+                    id: post.id,
+                    name: post.name,
+                    url: format!("{}{}", self.config.base_url, post.permalink),
+                });
+            }
+        }
+
+        Err(RouxError::MediaProcessing(title))
+    }
+
+    /// The user's name, looked up if the login did not already.
+    #[maybe_async::maybe_async]
+    async fn username(&self) -> Result<String, RouxError> {
+        match &self.config.username {
+            Some(username) => Ok(username.to_owned()),
+            None => Ok(self.me().await?.name),
+        }
+    }
+
+    /// The newest posts of `username`.
+    #[maybe_async::maybe_async]
+    async fn latest_submissions(&self, username: &str) -> Result<Submissions, RouxError> {
+        let url = format!(
+            "{}?sort=new&limit=25",
+            url::build(
+                &self.config.oauth_url,
+                &format!("user/{}/submitted", username)
+            )
+        );
+
+        Ok(self.session.get(&url).await?.json::<Submissions>().await?)
+    }
+
+    /// Upload an image or video to Reddit's storage, to post with
+    /// [`SubmissionBuilder::image`] or [`SubmissionBuilder::video`].
+    #[maybe_async::maybe_async]
+    pub async fn upload_media(&self, media: &Media) -> Result<UploadedMedia, RouxError> {
+        let form = [
+            ("filepath", media.file_name()),
+            ("mimetype", media.mime_type()),
+        ];
+        let lease = self
+            .post("api/media/asset", form)
+            .await?
+            .json::<AssetLease>()
+            .await?;
+
+        let key = lease
+            .key()
+            .ok_or_else(|| missing_data("api/media/asset"))?
+            .to_owned();
+        let upload_url = lease.upload_url(self.config.upload_url.as_deref());
+        let (content_type, body) = lease.multipart(media);
+
+        // Not through the session, the storage must not see the access token.
+        let response = Client::builder()
+            .user_agent(&self.config.user_agent)
+            .build()?
+            .post(&upload_url)
+            .header("Content-Type", content_type)
+            .body(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(RouxError::from_response(response).await);
+        }
+
+        Ok(UploadedMedia {
+            asset_id: lease.asset.asset_id,
+            url: format!("{}/{}", upload_url, key),
+            kind: media.kind(),
+        })
+    }
+
+    /// Upload the images of a gallery and post it.
+    #[maybe_async::maybe_async]
+    pub async fn submit_gallery(&self, gallery: GalleryBuilder) -> Result<SubmitData, RouxError> {
+        let mut media_ids = Vec::new();
+        for media in gallery.media()? {
+            media_ids.push(self.upload_media(media).await?.asset_id);
+        }
+
+        let url = url::build(&self.config.oauth_url, "api/submit_gallery_post");
+        self.session
+            .post_json(&url, &gallery.body(media_ids))
+            .await?
+            .json::<ApiResponse<GallerySubmitted>>()
+            .await?
+            .into_result()
+            .map_err(RouxError::Api)?
+            .and_then(GallerySubmitted::into_submit_data)
+            .ok_or_else(|| missing_data("api/submit_gallery_post"))
+    }
+
+    /// Upload and post an image
+    #[maybe_async::maybe_async]
+    pub async fn submit_image(
+        &self,
+        title: &str,
+        image: Media,
+        sr: &str,
+    ) -> Result<SubmitData, RouxError> {
+        if image.kind() != MediaKind::Image {
+            return Err(not_a(&image, "an image"));
+        }
+
+        let image = self.upload_media(&image).await?;
+        self.submit(SubmissionBuilder::image(sr, title, &image))
+            .await
+    }
+
+    /// Upload and post a video, shown with the `poster` image until it is played
+    #[maybe_async::maybe_async]
+    pub async fn submit_video(
+        &self,
+        title: &str,
+        video: Media,
+        poster: Media,
+        sr: &str,
+    ) -> Result<SubmitData, RouxError> {
+        if video.kind() != MediaKind::Video {
+            return Err(not_a(&video, "a video"));
+        }
+        if poster.kind() != MediaKind::Image {
+            return Err(not_a(&poster, "an image"));
+        }
+
+        let video = self.upload_media(&video).await?;
+        let poster = self.upload_media(&poster).await?;
+        self.submit(SubmissionBuilder::video(sr, title, &video, &poster))
+            .await
+    }
+
     /// Submit link
     #[maybe_async::maybe_async]
     pub async fn submit_link(
@@ -68,15 +359,8 @@ impl Me {
         title: &str,
         link: &str,
         sr: &str,
-    ) -> Result<Response, RouxError> {
-        let form = [
-            ("kind", "link"),
-            ("title", title),
-            ("url", link),
-            ("sr", sr),
-        ];
-
-        self.post("api/submit", &form).await
+    ) -> Result<SubmitData, RouxError> {
+        self.submit(SubmissionBuilder::link(sr, title, link)).await
     }
 
     /// Submit text
@@ -86,15 +370,8 @@ impl Me {
         title: &str,
         text: &str,
         sr: &str,
-    ) -> Result<Response, RouxError> {
-        let form = [
-            ("kind", "self"),
-            ("title", title),
-            ("text", text),
-            ("sr", sr),
-        ];
-
-        self.post("api/submit", &form).await
+    ) -> Result<SubmitData, RouxError> {
+        self.submit(SubmissionBuilder::text(sr, title, text)).await
     }
 
     /// Submit richtext
@@ -104,15 +381,9 @@ impl Me {
         title: &str,
         richtext: &str,
         sr: &str,
-    ) -> Result<Response, RouxError> {
-        let form = [
-            ("kind", "self"),
-            ("title", title),
-            ("richtext_json", richtext),
-            ("sr", sr),
-        ];
-
-        self.post("api/submit", &form).await
+    ) -> Result<SubmitData, RouxError> {
+        self.submit(SubmissionBuilder::richtext(sr, title, richtext))
+            .await
     }
 
     /// Adds a friend to a subreddit with the specified type
@@ -156,7 +427,7 @@ impl Me {
         username: &str,
         subject: &str,
         body: &str,
-    ) -> Result<Response, RouxError> {
+    ) -> Result<(), RouxError> {
         let form = [
             ("api_type", "json"),
             ("subject", subject),
@@ -164,7 +435,9 @@ impl Me {
             ("to", username),
         ];
 
-        self.post("api/compose", &form).await
+        self.post_api::<_, serde_json::Value>("api/compose", &form)
+            .await?;
+        Ok(())
     }
 
     /// Get user's submitted posts.
@@ -176,13 +449,11 @@ impl Me {
     /// Get saved
     #[maybe_async::maybe_async]
     pub async fn saved(&self, options: Option<FeedOption>) -> Result<Saved, RouxError> {
-        let url = &mut format!(
-            "user/{}/saved/.json?",
-            self.config.username.to_owned().unwrap()
-        );
+        let username = self.username().await?;
+        let url = &mut format!("user/{}/saved/.json?", username);
 
         if let Some(options) = options {
-            options.build_url(url);
+            options.build_url(url)?;
         }
 
         Ok(self.get(url).await?.json::<Saved>().await?)
@@ -191,13 +462,11 @@ impl Me {
     /// Get upvoted
     #[maybe_async::maybe_async]
     pub async fn upvoted(&self, options: Option<FeedOption>) -> Result<Saved, RouxError> {
-        let url = &mut format!(
-            "user/{}/upvoted/.json?",
-            self.config.username.to_owned().unwrap()
-        );
+        let username = self.username().await?;
+        let url = &mut format!("user/{}/upvoted/.json?", username);
 
         if let Some(options) = options {
-            options.build_url(url);
+            options.build_url(url)?;
         }
 
         Ok(self.get(url).await?.json::<Saved>().await?)
@@ -206,13 +475,11 @@ impl Me {
     /// Get downvoted
     #[maybe_async::maybe_async]
     pub async fn downvoted(&self, options: Option<FeedOption>) -> Result<Saved, RouxError> {
-        let url = &mut format!(
-            "user/{}/downvoted/.json?",
-            self.config.username.to_owned().unwrap()
-        );
+        let username = self.username().await?;
+        let url = &mut format!("user/{}/downvoted/.json?", username);
 
         if let Some(options) = options {
-            options.build_url(url);
+            options.build_url(url)?;
         }
 
         Ok(self.get(url).await?.json::<Saved>().await?)
@@ -224,51 +491,250 @@ impl Me {
         Ok(self.get("message/unread").await?.json::<Inbox>().await?)
     }
 
+    /// Follow the inbox as mentions, replies and messages arrive, oldest first. See the
+    /// [`stream`](crate::util::stream) module for how polling works.
+    pub fn stream_inbox(
+        &self,
+        options: StreamOptions,
+        inbox: InboxOptions,
+    ) -> LiveStream<'_, Thing> {
+        let poller = Poller::new(options, |item: &Thing| item.fullname());
+        if inbox.marks_read() {
+            LiveStream::with_ack(
+                poller,
+                move || self.poll_inbox(inbox.to_owned()),
+                move |item| self.mark_yielded(item),
+            )
+        } else {
+            LiveStream::new(poller, move || self.poll_inbox(inbox.to_owned()))
+        }
+    }
+
+    /// Get the unread items `options` yields.
+    #[maybe_async::maybe_async]
+    async fn poll_inbox(&self, options: InboxOptions) -> Result<Inbox, RouxError> {
+        let mut unread = self.unread().await?;
+        unread.data.children.retain(|item| options.matches(item));
+        Ok(unread)
+    }
+
+    /// Mark an inbox item read as it is yielded.
+    #[maybe_async::maybe_async]
+    async fn mark_yielded(&self, item: Thing) -> Result<Thing, RouxError> {
+        if let Some(name) = item.fullname() {
+            self.mark_read(&[name.parse()?]).await?;
+        }
+
+        Ok(item)
+    }
+
     /// Mark messages as read
     #[maybe_async::maybe_async]
-    pub async fn mark_read(&self, ids: &str) -> Result<Response, RouxError> {
-        let form = [("id", ids)];
+    pub async fn mark_read(&self, ids: &[Fullname]) -> Result<Response, RouxError> {
+        let form = [("id", fullname::join(ids))];
         self.post("api/read_message", &form).await
     }
 
     /// Mark messages as unread
     #[maybe_async::maybe_async]
-    pub async fn mark_unread(&self, ids: &str) -> Result<Response, RouxError> {
-        let form = [("id", ids)];
+    pub async fn mark_unread(&self, ids: &[Fullname]) -> Result<Response, RouxError> {
+        let form = [("id", fullname::join(ids))];
         self.post("api/unread_message", &form).await
     }
 
-    /// Comment
+    /// Comment, and return the new comment
     #[maybe_async::maybe_async]
-    pub async fn comment(&self, text: &str, parent: &str) -> Result<Response, RouxError> {
-        let form = [("text", text), ("parent", parent)];
-        self.post("api/comment", &form).await
+    pub async fn comment(&self, text: &str, parent: &Fullname) -> Result<CommentData, RouxError> {
+        let parent = parent.to_string();
+        let form = [("api_type", "json"), ("text", text), ("parent", &parent)];
+        self.post_api::<_, ThingsData<CommentData>>("api/comment", &form)
+            .await?
+            .and_then(|data| data.things.into_iter().next())
+            .map(|thing| thing.data)
+            .ok_or_else(|| missing_data("api/comment"))
     }
 
     /// Edit a 'thing'
     #[maybe_async::maybe_async]
-    pub async fn edit(&self, text: &str, parent: &str) -> Result<Response, RouxError> {
-        let form = [("text", text), ("thing_id", parent)];
-        self.post("api/editusertext", &form).await
+    pub async fn edit(&self, text: &str, parent: &Fullname) -> Result<(), RouxError> {
+        let parent = parent.to_string();
+        let form = [("api_type", "json"), ("text", text), ("thing_id", &parent)];
+        self.post_api::<_, serde_json::Value>("api/editusertext", &form)
+            .await?;
+        Ok(())
+    }
+
+    /// Vote on a post or comment
+    #[maybe_async::maybe_async]
+    pub async fn vote(&self, id: &Fullname, direction: VoteDirection) -> Result<(), RouxError> {
+        let form = [("id", id.to_string()), ("dir", direction.to_string())];
+        self.post_action("api/vote", &form).await
+    }
+
+    /// Save a post or comment, optionally in a category (Reddit Premium only)
+    #[maybe_async::maybe_async]
+    pub async fn save(&self, id: &Fullname, category: Option<&str>) -> Result<(), RouxError> {
+        let mut form = vec![("id", id.to_string())];
+        if let Some(category) = category {
+            form.push(("category", category.to_owned()));
+        }
+        self.post_action("api/save", &form).await
+    }
+
+    /// Unsave a post or comment
+    #[maybe_async::maybe_async]
+    pub async fn unsave(&self, id: &Fullname) -> Result<(), RouxError> {
+        let form = [("id", id.to_string())];
+        self.post_action("api/unsave", &form).await
+    }
+
+    /// Hide posts
+    #[maybe_async::maybe_async]
+    pub async fn hide(&self, ids: &[Fullname]) -> Result<(), RouxError> {
+        let form = [("id", fullname::join(ids))];
+        self.post_action("api/hide", &form).await
+    }
+
+    /// Unhide posts
+    #[maybe_async::maybe_async]
+    pub async fn unhide(&self, ids: &[Fullname]) -> Result<(), RouxError> {
+        let form = [("id", fullname::join(ids))];
+        self.post_action("api/unhide", &form).await
+    }
+
+    /// Report a post, comment or message
+    /// `reason`: why, at most 100 characters
+    /// `rule`: the subreddit rule it breaks, if any
+    #[maybe_async::maybe_async]
+    pub async fn report(
+        &self,
+        id: &Fullname,
+        reason: &str,
+        rule: Option<&str>,
+    ) -> Result<(), RouxError> {
+        let mut form = vec![
+            ("api_type", "json".to_owned()),
+            ("thing_id", id.to_string()),
+            ("reason", reason.to_owned()),
+        ];
+        if let Some(rule) = rule {
+            form.push(("rule_reason", rule.to_owned()));
+        }
+        self.post_action("api/report", &form).await
+    }
+
+    /// Delete a post or comment
+    #[maybe_async::maybe_async]
+    pub async fn delete(&self, id: &Fullname) -> Result<(), RouxError> {
+        let form = [("id", id.to_string())];
+        self.post_action("api/del", &form).await
+    }
+
+    /// Lock a post or comment, so it cannot be replied to (moderators only)
+    #[maybe_async::maybe_async]
+    pub async fn lock(&self, id: &Fullname) -> Result<(), RouxError> {
+        let form = [("id", id.to_string())];
+        self.post_action("api/lock", &form).await
+    }
+
+    /// Unlock a post or comment (moderators only)
+    #[maybe_async::maybe_async]
+    pub async fn unlock(&self, id: &Fullname) -> Result<(), RouxError> {
+        let form = [("id", id.to_string())];
+        self.post_action("api/unlock", &form).await
+    }
+
+    /// Mark a post NSFW
+    #[maybe_async::maybe_async]
+    pub async fn mark_nsfw(&self, id: &Fullname) -> Result<(), RouxError> {
+        let form = [("id", id.to_string())];
+        self.post_action("api/marknsfw", &form).await
+    }
+
+    /// Unmark a post NSFW
+    #[maybe_async::maybe_async]
+    pub async fn unmark_nsfw(&self, id: &Fullname) -> Result<(), RouxError> {
+        let form = [("id", id.to_string())];
+        self.post_action("api/unmarknsfw", &form).await
+    }
+
+    /// Mark a post as a spoiler
+    #[maybe_async::maybe_async]
+    pub async fn spoiler(&self, id: &Fullname) -> Result<(), RouxError> {
+        let form = [("id", id.to_string())];
+        self.post_action("api/spoiler", &form).await
+    }
+
+    /// Unmark a post as a spoiler
+    #[maybe_async::maybe_async]
+    pub async fn unspoiler(&self, id: &Fullname) -> Result<(), RouxError> {
+        let form = [("id", id.to_string())];
+        self.post_action("api/unspoiler", &form).await
+    }
+
+    /// Turn inbox notifications for replies to a post or comment on or off
+    #[maybe_async::maybe_async]
+    pub async fn send_replies(&self, id: &Fullname, state: bool) -> Result<(), RouxError> {
+        let form = [("id", id.to_string()), ("state", state.to_string())];
+        self.post_action("api/sendreplies", &form).await
+    }
+
+    /// Set how the comments of a post are sorted by default, or go back to the subreddit's
+    /// default with `None`
+    #[maybe_async::maybe_async]
+    pub async fn set_suggested_sort(
+        &self,
+        id: &Fullname,
+        sort: Option<CommentSort>,
+    ) -> Result<(), RouxError> {
+        let form = [
+            ("api_type", "json"),
+            ("id", &id.to_string()),
+            (
+                "sort",
+                sort.as_ref().map_or("", CommentSort::get_string_for_sort),
+            ),
+        ];
+        self.post_action("api/set_suggested_sort", form).await
     }
 
     /// Get submissions by id
-    /// `ids`: the fullnames of submisions to get, comma separated
+    /// `ids`: the fullnames of submisions to get
     #[maybe_async::maybe_async]
-    pub async fn get_submissions(&self, ids: &str) -> Result<Submissions, RouxError> {
-        let url = format!("/by_id/{ids}");
+    pub async fn get_submissions(&self, ids: &[Fullname]) -> Result<Submissions, RouxError> {
+        let url = format!("/by_id/{}", fullname::join(ids));
         Ok(self.get(&url).await?.json::<Submissions>().await?)
     }
 
+    /// Get a post with its comments.
+    #[maybe_async::maybe_async]
+    pub async fn article(
+        &self,
+        article: &str,
+        options: Option<ArticleOption>,
+    ) -> Result<Article, RouxError> {
+        let url = &mut format!(
+            "{}?",
+            url::build(&self.config.oauth_url, &format!("comments/{}", article))
+        );
+
+        if let Some(options) = options {
+            options.build_url(url)?;
+        }
+
+        Ok(self.session.get(url).await?.json::<Article>().await?)
+    }
+
     /// Logout
     #[maybe_async::maybe_async]
     pub async fn logout(self) -> Result<(), RouxError> {
-        let url = "https://www.reddit.com/api/v1/revoke_token";
+        let url = &format!("{}/api/v1/revoke_token", self.config.base_url);
 
-        let form = [("access_token", self.config.access_token.to_owned())];
+        let form = [("access_token", self.session.access_token())];
 
         let response = self
-            .client
+            .session
+            .client()
             .post(url)
             .basic_auth(&self.config.client_id, Some(&self.config.client_secret))
             .form(&form)
@@ -278,7 +744,46 @@ impl Me {
         if response.status() == 204 {
             Ok(())
         } else {
-            Err(RouxError::Status(response))
+            Err(RouxError::from_response(response).await)
         }
     }
 }
+
+/// Which way to vote.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VoteDirection {
+    /// Upvote
+    Up,
+    /// Downvote
+    Down,
+    /// Take back a vote
+    Clear,
+}
+
+impl fmt::Display for VoteDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VoteDirection::Up => f.write_str("1"),
+            VoteDirection::Down => f.write_str("-1"),
+            VoteDirection::Clear => f.write_str("0"),
+        }
+    }
+}
+
+/// How often to look for a media post Reddit has not created yet.
+const MEDIA_POLLS: u32 = 20;
+
+/// How long to wait between looking for a media post.
+const MEDIA_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+fn not_a(media: &Media, kind: &str) -> RouxError {
+    RouxError::InvalidMedia(format!("{} is not {}", media.file_name(), kind))
+}
+
+/// The error for an `api_type=json` response that succeeded without the data it should have.
+fn missing_data(endpoint: &str) -> RouxError {
+    RouxError::from(<serde_json::Error as serde::de::Error>::custom(format!(
+        "{} returned no data",
+        endpoint
+    )))
+}
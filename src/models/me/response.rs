@@ -6,6 +6,8 @@ use serde::Deserialize;
 pub struct MeData {
     /// ID
     pub id: String,
+    /// Username
+    pub name: String,
     /// Is employee
     pub is_employee: bool,
     /// Verified
@@ -37,3 +39,14 @@ pub struct MeData {
     /// Icon img
     pub icon_img: String,
 }
+
+/// SubmitData
+#[derive(Debug, Deserialize)]
+pub struct SubmitData {
+    /// ID
+    pub id: String,
+    /// Fullname, e.g. `t3_abc123`
+    pub name: String,
+    /// URL
+    pub url: String,
+}
@@ -0,0 +1,478 @@
+//! # Submissions
+//! Everything `api/submit` takes, for [`Me::submit`](crate::Me::submit).
+//!
+//! ```no_run
+//! use roux::Reddit;
+//! use roux::SubmissionBuilder;
+//! # #[cfg(not(feature = "blocking"))]
+//! # use tokio;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! # let me = Reddit::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET").login().await.unwrap();
+//! let post = SubmissionBuilder::link("rust", "Announcing Rust 1.0", "https://blog.rust-lang.org/")
+//!     .flair_id("4d2a0dcc-5e40-11ec-8a3f-0e2b4fa4e3a5")
+//!     .spoiler(true)
+//!     .resubmit(false);
+//!
+//! let submitted = me.submit(post).await.unwrap();
+//! println!("{} is at {}", submitted.name, submitted.url);
+//! # }
+//! ```
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::models::me::media::{Media, MediaKind, UploadedMedia};
+use crate::models::me::response::SubmitData;
+use crate::util::{Fullname, Kind, RouxError};
+
+/// How many images a gallery holds.
+const GALLERY_LEN: std::ops::RangeInclusive<usize> = 2..=20;
+
+/// A post to submit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmissionBuilder {
+    sr: String,
+    title: String,
+    kind: &'static str,
+    url: Option<String>,
+    text: Option<String>,
+    richtext_json: Option<String>,
+    video_poster_url: Option<String>,
+    media: bool,
+    flair_id: Option<String>,
+    flair_text: Option<String>,
+    nsfw: bool,
+    spoiler: bool,
+    send_replies: bool,
+    resubmit: bool,
+    collection_id: Option<String>,
+    event: Option<(String, String, String)>,
+    chat: bool,
+}
+
+impl SubmissionBuilder {
+    fn new(sr: &str, title: &str, kind: &'static str) -> SubmissionBuilder {
+        SubmissionBuilder {
+            sr: sr.to_owned(),
+            title: title.to_owned(),
+            kind,
+            url: None,
+            text: None,
+            richtext_json: None,
+            video_poster_url: None,
+            media: false,
+            flair_id: None,
+            flair_text: None,
+            nsfw: false,
+            spoiler: false,
+            send_replies: true,
+            resubmit: true,
+            collection_id: None,
+            event: None,
+            chat: false,
+        }
+    }
+
+    /// A link post to subreddit `sr`.
+    pub fn link(sr: &str, title: &str, url: &str) -> SubmissionBuilder {
+        let mut builder = SubmissionBuilder::new(sr, title, "link");
+        builder.url = Some(url.to_owned());
+        builder
+    }
+
+    /// A text post to subreddit `sr`, with a markdown body.
+    pub fn text(sr: &str, title: &str, text: &str) -> SubmissionBuilder {
+        let mut builder = SubmissionBuilder::new(sr, title, "self");
+        builder.text = Some(text.to_owned());
+        builder
+    }
+
+    /// A text post to subreddit `sr`, with a body in Reddit's richtext JSON.
+    pub fn richtext(sr: &str, title: &str, richtext_json: &str) -> SubmissionBuilder {
+        let mut builder = SubmissionBuilder::new(sr, title, "self");
+        builder.richtext_json = Some(richtext_json.to_owned());
+        builder
+    }
+
+    /// An image post to subreddit `sr`, see [`Me::upload_media`](crate::Me::upload_media).
+    pub fn image(sr: &str, title: &str, image: &UploadedMedia) -> SubmissionBuilder {
+        let mut builder = SubmissionBuilder::new(sr, title, "image");
+        builder.url = Some(image.url.to_owned());
+        builder.media = true;
+        builder
+    }
+
+    /// A video post to subreddit `sr`, shown with the `poster` image until it is played. See
+    /// [`Me::upload_media`](crate::Me::upload_media).
+    pub fn video(
+        sr: &str,
+        title: &str,
+        video: &UploadedMedia,
+        poster: &UploadedMedia,
+    ) -> SubmissionBuilder {
+        let mut builder = SubmissionBuilder::new(sr, title, "video");
+        builder.url = Some(video.url.to_owned());
+        builder.video_poster_url = Some(poster.url.to_owned());
+        builder.media = true;
+        builder
+    }
+
+    /// Set the id of the subreddit's flair template to use.
+    pub fn flair_id(mut self, flair_id: &str) -> SubmissionBuilder {
+        self.flair_id = Some(flair_id.to_owned());
+        self
+    }
+
+    /// Set the flair text, for templates that allow editing it.
+    pub fn flair_text(mut self, flair_text: &str) -> SubmissionBuilder {
+        self.flair_text = Some(flair_text.to_owned());
+        self
+    }
+
+    /// Set whether the post is NSFW.
+    pub fn nsfw(mut self, nsfw: bool) -> SubmissionBuilder {
+        self.nsfw = nsfw;
+        self
+    }
+
+    /// Set whether the post is a spoiler.
+    pub fn spoiler(mut self, spoiler: bool) -> SubmissionBuilder {
+        self.spoiler = spoiler;
+        self
+    }
+
+    /// Set whether replies are sent to the inbox. On by default.
+    pub fn send_replies(mut self, send_replies: bool) -> SubmissionBuilder {
+        self.send_replies = send_replies;
+        self
+    }
+
+    /// Set whether a link that was already posted to the subreddit may be posted again. On
+    /// by default; when off, Reddit fails with `ALREADY_SUB`.
+    pub fn resubmit(mut self, resubmit: bool) -> SubmissionBuilder {
+        self.resubmit = resubmit;
+        self
+    }
+
+    /// Set the collection to add the post to.
+    pub fn collection_id(mut self, collection_id: &str) -> SubmissionBuilder {
+        self.collection_id = Some(collection_id.to_owned());
+        self
+    }
+
+    /// Make the post an event. `start` and `end` are local times like
+    /// `2024-05-01T18:00:00`, `tz` is a time zone like `Europe/Berlin`.
+    pub fn event(mut self, start: &str, end: &str, tz: &str) -> SubmissionBuilder {
+        self.event = Some((start.to_owned(), end.to_owned(), tz.to_owned()));
+        self
+    }
+
+    /// Set whether the comments are shown as a live chat.
+    pub fn chat(mut self, chat: bool) -> SubmissionBuilder {
+        self.chat = chat;
+        self
+    }
+
+    /// The subreddit to post to.
+    pub(crate) fn sr(&self) -> &str {
+        &self.sr
+    }
+
+    /// The title.
+    pub(crate) fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Whether this is an image or video post, which Reddit creates once the media has
+    /// been processed.
+    pub(crate) fn is_media(&self) -> bool {
+        self.media
+    }
+
+    /// The `api/submit` form.
+    pub(crate) fn form(self) -> Vec<(&'static str, String)> {
+        let mut form = vec![
+            ("api_type", "json".to_owned()),
+            ("kind", self.kind.to_owned()),
+            ("sr", self.sr),
+            ("title", self.title),
+        ];
+
+        let fields = [
+            ("url", self.url),
+            ("text", self.text),
+            ("richtext_json", self.richtext_json),
+            ("video_poster_url", self.video_poster_url),
+            ("flair_id", self.flair_id),
+            ("flair_text", self.flair_text),
+            ("collection_id", self.collection_id),
+        ];
+        form.extend(
+            fields
+                .into_iter()
+                .filter_map(|(key, value)| Some((key, value?))),
+        );
+
+        form.push(("nsfw", self.nsfw.to_string()));
+        form.push(("spoiler", self.spoiler.to_string()));
+        form.push(("sendreplies", self.send_replies.to_string()));
+        form.push(("resubmit", self.resubmit.to_string()));
+
+        if let Some((start, end, tz)) = self.event {
+            form.push(("event_start", start));
+            form.push(("event_end", end));
+            form.push(("event_tz", tz));
+        }
+
+        if self.chat {
+            form.push(("discussion_type", "CHAT".to_owned()));
+        }
+
+        form
+    }
+}
+
+/// A gallery post to submit with [`Me::submit_gallery`](crate::Me::submit_gallery), which
+/// uploads the images first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GalleryBuilder {
+    sr: String,
+    title: String,
+    items: Vec<GalleryItem>,
+    flair_id: Option<String>,
+    flair_text: Option<String>,
+    nsfw: bool,
+    spoiler: bool,
+    send_replies: bool,
+}
+
+/// An image in a [`GalleryBuilder`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GalleryItem {
+    media: Media,
+    caption: Option<String>,
+    outbound_url: Option<String>,
+}
+
+impl GalleryItem {
+    /// An image without a caption or link.
+    pub fn new(media: Media) -> GalleryItem {
+        GalleryItem {
+            media,
+            caption: None,
+            outbound_url: None,
+        }
+    }
+
+    /// Set the caption, at most 180 characters.
+    pub fn caption(mut self, caption: &str) -> GalleryItem {
+        self.caption = Some(caption.to_owned());
+        self
+    }
+
+    /// Set the URL the image links to.
+    pub fn outbound_url(mut self, outbound_url: &str) -> GalleryItem {
+        self.outbound_url = Some(outbound_url.to_owned());
+        self
+    }
+}
+
+impl GalleryBuilder {
+    /// A gallery post to subreddit `sr`. It needs 2 to 20 images.
+    pub fn new(sr: &str, title: &str) -> GalleryBuilder {
+        GalleryBuilder {
+            sr: sr.to_owned(),
+            title: title.to_owned(),
+            items: Vec::new(),
+            flair_id: None,
+            flair_text: None,
+            nsfw: false,
+            spoiler: false,
+            send_replies: true,
+        }
+    }
+
+    /// Add an image.
+    pub fn item(mut self, item: GalleryItem) -> GalleryBuilder {
+        self.items.push(item);
+        self
+    }
+
+    /// Set the id of the subreddit's flair template to use.
+    pub fn flair_id(mut self, flair_id: &str) -> GalleryBuilder {
+        self.flair_id = Some(flair_id.to_owned());
+        self
+    }
+
+    /// Set the flair text, for templates that allow editing it.
+    pub fn flair_text(mut self, flair_text: &str) -> GalleryBuilder {
+        self.flair_text = Some(flair_text.to_owned());
+        self
+    }
+
+    /// Set whether the post is NSFW.
+    pub fn nsfw(mut self, nsfw: bool) -> GalleryBuilder {
+        self.nsfw = nsfw;
+        self
+    }
+
+    /// Set whether the post is a spoiler.
+    pub fn spoiler(mut self, spoiler: bool) -> GalleryBuilder {
+        self.spoiler = spoiler;
+        self
+    }
+
+    /// Set whether replies are sent to the inbox. On by default.
+    pub fn send_replies(mut self, send_replies: bool) -> GalleryBuilder {
+        self.send_replies = send_replies;
+        self
+    }
+
+    /// The images to upload, after checking there are enough of them and they are images.
+    pub(crate) fn media(&self) -> Result<impl Iterator<Item = &Media>, RouxError> {
+        if !GALLERY_LEN.contains(&self.items.len()) {
+            return Err(RouxError::InvalidMedia(format!(
+                "a gallery needs 2 to 20 images, not {}",
+                self.items.len()
+            )));
+        }
+
+        if let Some(item) = self
+            .items
+            .iter()
+            .find(|item| item.media.kind() != MediaKind::Image)
+        {
+            return Err(RouxError::InvalidMedia(format!(
+                "{} is not an image",
+                item.media.file_name()
+            )));
+        }
+
+        Ok(self.items.iter().map(|item| &item.media))
+    }
+
+    /// The `api/submit_gallery_post` body, with the ids Reddit gave the uploaded images.
+    pub(crate) fn body(self, media_ids: Vec<String>) -> Value {
+        let items: Vec<Value> = self
+            .items
+            .into_iter()
+            .zip(media_ids)
+            .map(|(item, media_id)| {
+                json!({
+                    "media_id": media_id,
+                    "caption": item.caption.unwrap_or_default(),
+                    "outbound_url": item.outbound_url.unwrap_or_default(),
+                })
+            })
+            .collect();
+
+        let mut body = json!({
+            "api_type": "json",
+            "show_error_list": true,
+            "sr": self.sr,
+            "title": self.title,
+            "items": items,
+            "nsfw": self.nsfw,
+            "spoiler": self.spoiler,
+            "sendreplies": self.send_replies,
+        });
+        if let Some(flair_id) = self.flair_id {
+            body["flair_id"] = flair_id.into();
+        }
+        if let Some(flair_text) = self.flair_text {
+            body["flair_text"] = flair_text.into();
+        }
+
+        body
+    }
+}
+
+/// The `json.data` of `api/submit_gallery_post`, which only has the post's URL.
+#[derive(Debug, Deserialize)]
+pub(crate) struct GallerySubmitted {
+    url: String,
+}
+
+impl GallerySubmitted {
+    /// The post, with the id taken from its URL.
+    pub(crate) fn into_submit_data(self) -> Option<SubmitData> {
+        let mut segments = self.url.split('/');
+        segments.find(|segment| *segment == "comments")?;
+        let name = Fullname::new(Kind::Link, segments.next()?).ok()?;
+
+        Some(SubmitData {
+            id: name.id().to_owned(),
+            name: name.to_string(),
+            url: self.url,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GalleryBuilder, GalleryItem, SubmissionBuilder};
+    use crate::models::me::media::Media;
+    use crate::util::RouxError;
+
+    #[test]
+    fn test_form() {
+        let form = SubmissionBuilder::link("rust", "Title", "https://example.com")
+            .flair_text("News")
+            .nsfw(true)
+            .resubmit(false)
+            .event("2024-05-01T18:00:00", "2024-05-01T20:00:00", "UTC")
+            .form();
+
+        let form: Vec<(&str, &str)> = form.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        assert_eq!(
+            form,
+            [
+                ("api_type", "json"),
+                ("kind", "link"),
+                ("sr", "rust"),
+                ("title", "Title"),
+                ("url", "https://example.com"),
+                ("flair_text", "News"),
+                ("nsfw", "true"),
+                ("spoiler", "false"),
+                ("sendreplies", "true"),
+                ("resubmit", "false"),
+                ("event_start", "2024-05-01T18:00:00"),
+                ("event_end", "2024-05-01T20:00:00"),
+                ("event_tz", "UTC"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gallery() {
+        let image = |name: &str| Media::from_bytes(name, b"GIF89a".to_vec()).unwrap();
+
+        let gallery = GalleryBuilder::new("rust", "Title").item(GalleryItem::new(image("a.gif")));
+        assert!(matches!(gallery.media(), Err(RouxError::InvalidMedia(_))));
+
+        let gallery = gallery
+            .item(
+                GalleryItem::new(image("b.gif"))
+                    .caption("B")
+                    .outbound_url("https://example.com"),
+            )
+            .flair_id("f1");
+        let names: Vec<&str> = gallery.media().unwrap().map(Media::file_name).collect();
+        assert_eq!(names, ["a.gif", "b.gif"]);
+
+        let body = gallery.body(vec!["m1".to_owned(), "m2".to_owned()]);
+        assert_eq!(body["flair_id"], "f1");
+        assert_eq!(body["sendreplies"], true);
+        assert_eq!(
+            body["items"],
+            serde_json::json!([
+                {"media_id": "m1", "caption": "", "outbound_url": ""},
+                {"media_id": "m2", "caption": "B", "outbound_url": "https://example.com"}
+            ])
+        );
+    }
+}
@@ -1,4 +1,6 @@
 pub mod about;
+pub mod app_only;
+pub mod article;
 pub mod comment;
 pub mod friend;
 pub mod inbox;
@@ -10,12 +12,17 @@ pub mod response;
 pub mod saved;
 pub mod submission;
 pub mod subreddit;
+pub mod thing;
 pub mod user;
 
 pub use about::About;
+pub use app_only::AppOnly;
+pub use article::Article;
+pub use comment::tree::{CommentNode, CommentTree};
 pub use comment::Comments;
 pub use friend::Friend;
 pub use inbox::Inbox;
+pub use me::submit::{GalleryBuilder, GalleryItem, SubmissionBuilder};
 pub use me::Me;
 pub use moderator::Moderators;
 pub use overview::Overview;
@@ -23,4 +30,5 @@ pub use reply::{MaybeReplies, Replies};
 pub use saved::Saved;
 pub use submission::Submissions;
 pub use subreddit::{Subreddit, Subreddits};
+pub use thing::{Thing, Things};
 pub use user::User;
@@ -1,11 +1,10 @@
 //! # Subreddit Comment Responses
 use serde::{Deserialize, Serialize};
 
-use crate::models::comment::CommentData;
-use crate::models::response::BasicListing;
+use crate::models::thing::Things;
 
-/// Doc
-pub type Replies = BasicListing<CommentData>;
+/// Replies to a comment, and `more` stubs for the replies that were left out
+pub type Replies = Things;
 
 /// Replies can be more comments or an empty string
 #[derive(Debug, Serialize, Deserialize)]
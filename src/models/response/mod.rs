@@ -3,6 +3,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::util::RedditApiError;
+
 /// Basic structure of a Reddit response.
 /// See: <https://github.com/reddit-archive/reddit/wiki/JSON>
 #[derive(Serialize, Deserialize, Debug)]
@@ -31,3 +33,39 @@ pub struct Listing<T> {
 
 /// Often times a basic thing will have this structure.
 pub type BasicListing<T> = BasicThing<Listing<BasicThing<T>>>;
+
+/// The body of an `api_type=json` response. Reddit answers `200 OK` even when it refuses
+/// to act, listing what went wrong in `errors`.
+#[derive(Deserialize, Debug)]
+pub struct ApiResponse<T> {
+    /// The response.
+    pub json: ApiResponseData<T>,
+}
+
+/// The contents of an [`ApiResponse`].
+#[derive(Deserialize, Debug)]
+pub struct ApiResponseData<T> {
+    /// Errors, empty if the request succeeded.
+    #[serde(default)]
+    pub errors: Vec<RedditApiError>,
+    /// The result, for endpoints that have one.
+    pub data: Option<T>,
+}
+
+impl<T> ApiResponse<T> {
+    /// The result, or the errors Reddit reported.
+    pub fn into_result(self) -> Result<Option<T>, Vec<RedditApiError>> {
+        if self.json.errors.is_empty() {
+            Ok(self.json.data)
+        } else {
+            Err(self.json.errors)
+        }
+    }
+}
+
+/// The `data` of an [`ApiResponse`] from endpoints that create or edit things.
+#[derive(Deserialize, Debug)]
+pub struct ThingsData<T> {
+    /// The things that were created or edited.
+    pub things: Vec<BasicThing<T>>,
+}
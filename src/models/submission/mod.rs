@@ -1,4 +1,6 @@
 //! # Subreddit Submission Responses
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -144,6 +146,12 @@ pub struct SubmissionData {
     pub visited: bool,
     /// The number of reports, if the user is a moderator of this subreddit.
     pub num_reports: Option<u64>,
+    /// This is `Some(true)` if this is a gallery post.
+    pub is_gallery: Option<bool>,
+    /// The images of a gallery post, in order, with their captions and links.
+    pub gallery_data: Option<SubmissionDataGallery>,
+    /// The media of a gallery post, or images inlined in a text post, by media id.
+    pub media_metadata: Option<HashMap<String, SubmissionDataMediaMetadata>>,
 }
 
 /// SubmissionDataPreview
@@ -178,5 +186,65 @@ pub struct SubmissionDataPreviewImageSource {
     pub height: u64,
 }
 
+/// SubmissionDataGallery
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubmissionDataGallery {
+    /// The images, in the order they are shown.
+    pub items: Vec<SubmissionDataGalleryItem>,
+}
+
+/// SubmissionDataGalleryItem
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubmissionDataGalleryItem {
+    /// The key of the image in `media_metadata`.
+    pub media_id: String,
+    /// Gallery Item ID
+    pub id: u64,
+    /// The caption, if any.
+    pub caption: Option<String>,
+    /// The URL the image links to, if any.
+    pub outbound_url: Option<String>,
+}
+
+/// SubmissionDataMediaMetadata
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubmissionDataMediaMetadata {
+    /// Whether Reddit has processed the media, e.g. `valid`, or `unprocessed` for media that
+    /// has no other fields yet.
+    pub status: String,
+    /// The kind of media, e.g. `Image` or `AnimatedImage`.
+    #[serde(rename = "e")]
+    pub kind: Option<String>,
+    /// The MIME type, e.g. `image/png`.
+    #[serde(rename = "m")]
+    pub mime_type: Option<String>,
+    /// The media at full size.
+    #[serde(rename = "s")]
+    pub source: Option<SubmissionDataMediaSource>,
+    /// Smaller previews of the media.
+    #[serde(rename = "p", default)]
+    pub previews: Vec<SubmissionDataMediaSource>,
+    /// Media ID
+    pub id: Option<String>,
+}
+
+/// SubmissionDataMediaSource
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubmissionDataMediaSource {
+    /// URL of an image, with `&` escaped as `&amp;`.
+    #[serde(rename = "u")]
+    pub url: Option<String>,
+    /// URL of an animated image as a GIF.
+    pub gif: Option<String>,
+    /// URL of an animated image as an MP4 video.
+    pub mp4: Option<String>,
+    /// Width
+    #[serde(rename = "x")]
+    pub width: u64,
+    /// Height
+    #[serde(rename = "y")]
+    pub height: u64,
+}
+
 /// Submissions
 pub type Submissions = BasicListing<SubmissionData>;
@@ -32,6 +32,9 @@
 //! // Get comments from a submission.
 //! let article_id = &hot.unwrap().data.children.first().unwrap().data.id.clone();
 //! let article_comments = subreddit.article_comments(article_id, None, Some(25));
+//!
+//! // Get a submission together with its comments.
+//! let article = subreddit.article(article_id, None);
 //! # }
 //! ```
 //!
@@ -63,16 +66,34 @@
 //! let next_hot = subreddit.hot(25, Some(after_options)).await;
 //! # }
 //! ```
+//!
+//! A [`Paginator`](crate::util::Paginator) does this for you, yielding posts one by one
+//! across pages.
 pub mod response;
 extern crate serde_json;
 
+use std::collections::HashSet;
+
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
+
 use crate::models::subreddit::response::{SubredditData, SubredditResponse, SubredditsData};
 
 use crate::client::Client;
+use crate::session::Session;
 use crate::util::defaults::default_client;
-use crate::util::{FeedOption, RouxError};
-
-use crate::models::{Comments, Moderators, Submissions};
+use crate::util::option::{check_limit, encode};
+use crate::util::stream::Poller;
+use crate::util::{
+    url, ArticleOption, FeedOption, LiveStream, RateLimit, RetryPolicy, RouxError, StreamOptions,
+};
+
+use crate::models::comment::tree::{CommentTree, MORE_CHILDREN_LEN};
+use crate::models::comment::CommentData;
+use crate::models::response::ApiResponse;
+use crate::models::submission::SubmissionData;
+use crate::models::thing::{Thing, Things};
+use crate::models::{Article, Comments, Moderators, Submissions};
 
 /// Access subreddits API
 pub struct Subreddits;
@@ -85,45 +106,82 @@ impl Subreddits {
         limit: Option<u32>,
         options: Option<FeedOption>,
     ) -> Result<SubredditsData, RouxError> {
-        let url = &mut format!("https://www.reddit.com/subreddits/search.json?q={}", name);
+        Subreddits::search_with_base(url::BASE_URL, name, limit, options).await
+    }
 
-        if let Some(limit) = limit {
+    /// Search subreddits on `base_url` instead of `https://www.reddit.com`.
+    #[maybe_async::maybe_async]
+    pub async fn search_with_base(
+        base_url: &str,
+        name: &str,
+        limit: Option<u32>,
+        options: Option<FeedOption>,
+    ) -> Result<SubredditsData, RouxError> {
+        let session = Session::new(&default_client());
+        Subreddits::search_with(&session, base_url, name, limit, options).await
+    }
+
+    #[maybe_async::maybe_async]
+    pub(crate) async fn search_with(
+        session: &Session,
+        base_url: &str,
+        name: &str,
+        limit: Option<u32>,
+        options: Option<FeedOption>,
+    ) -> Result<SubredditsData, RouxError> {
+        let url = &mut format!(
+            "{}/subreddits/search.json?q={}",
+            base_url.trim_end_matches('/'),
+            encode(name)
+        );
+
+        // A limit set in `options` takes the place of `limit`.
+        if let Some(limit) = limit.filter(|_| !has_limit(&options)) {
+            check_limit(limit)?;
             url.push_str(&format!("&limit={}", limit));
         }
 
         if let Some(options) = options {
-            options.build_url(url);
+            options.build_url(url)?;
         }
 
-        let client = default_client();
-
-        Ok(client
-            .get(&url.to_owned())
-            .send()
-            .await?
-            .json::<SubredditsData>()
-            .await?)
+        Ok(session.get(url).await?.json::<SubredditsData>().await?)
     }
 }
 
+/// The `data` of an `/api/morechildren` response.
+#[derive(Deserialize)]
+struct MoreChildrenData {
+    things: Vec<Thing>,
+}
+
+/// Whether `options` sets its own limit.
+fn has_limit(options: &Option<FeedOption>) -> bool {
+    options
+        .as_ref()
+        .is_some_and(|options| options.limit.is_some())
+}
+
 /// Subreddit
 pub struct Subreddit {
     /// Name of subreddit.
     pub name: String,
+    base_url: String,
     url: String,
-    client: Client,
+    session: Session,
     is_oauth: bool,
 }
 
 impl Subreddit {
     /// Create a new `Subreddit` instance.
     pub fn new(name: &str) -> Subreddit {
-        let subreddit_url = format!("https://www.reddit.com/r/{}", name);
+        let subreddit_url = format!("{}/r/{}", url::BASE_URL, name);
 
         Subreddit {
             name: name.to_owned(),
+            base_url: url::BASE_URL.to_owned(),
             url: subreddit_url,
-            client: default_client(),
+            session: Session::new(&default_client()),
             is_oauth: false,
         }
     }
@@ -131,24 +189,48 @@ impl Subreddit {
     /// Create a new authenticated `Subreddit` instance using an oauth client
     /// from the `Reddit` module.
     pub fn new_oauth(name: &str, client: &Client) -> Subreddit {
-        let subreddit_url = format!("https://oauth.reddit.com/r/{}", name);
+        Subreddit::from_session(name, Session::new(client))
+    }
+
+    /// Create a new authenticated `Subreddit` instance that shares `session`'s token.
+    pub(crate) fn from_session(name: &str, session: Session) -> Subreddit {
+        let subreddit_url = format!("{}/r/{}", url::OAUTH_URL, name);
 
         Subreddit {
             name: name.to_owned(),
+            base_url: url::OAUTH_URL.to_owned(),
             url: subreddit_url,
-            client: client.to_owned(),
+            session,
             is_oauth: true,
         }
     }
 
+    /// Sends requests to `base_url` instead of Reddit, e.g. a local server standing in for
+    /// it in tests.
+    pub fn base_url(mut self, base_url: &str) -> Subreddit {
+        self.base_url = base_url.trim_end_matches('/').to_owned();
+        self.url = format!("{}/r/{}", self.base_url, self.name);
+        self
+    }
+
+    /// Sets how requests that fail with a server error or a network error are retried.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Subreddit {
+        self.session = self.session.with_retry_policy(policy);
+        self
+    }
+
+    /// The request budget Reddit last reported for this subreddit's requests.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.session.rate_limit()
+    }
+
     /// Get moderators (requires authentication)
     #[maybe_async::maybe_async]
     pub async fn moderators(&self) -> Result<Moderators, RouxError> {
         if self.is_oauth {
             Ok(self
-                .client
+                .session
                 .get(&format!("{}/about/moderators/.json", self.url))
-                .send()
                 .await?
                 .json::<Moderators>()
                 .await?)
@@ -161,9 +243,8 @@ impl Subreddit {
     #[maybe_async::maybe_async]
     pub async fn about(&self) -> Result<SubredditData, RouxError> {
         Ok(self
-            .client
+            .session
             .get(&format!("{}/about/.json", self.url))
-            .send()
             .await?
             .json::<SubredditResponse>()
             .await?
@@ -177,28 +258,28 @@ impl Subreddit {
         limit: u32,
         options: Option<FeedOption>,
     ) -> Result<Submissions, RouxError> {
-        let url = &mut format!("{}/{}.json?limit={}", self.url, ty, limit);
+        let url = &mut format!("{}/{}.json?", self.url, ty);
+
+        // A limit set in `options` takes the place of `limit`.
+        if !has_limit(&options) {
+            check_limit(limit)?;
+            url.push_str(&format!("limit={}", limit));
+        }
 
         if let Some(options) = options {
-            options.build_url(url);
+            options.build_url(url)?;
         }
 
-        Ok(self
-            .client
-            .get(&url.to_owned())
-            .send()
-            .await?
-            .json::<Submissions>()
-            .await?)
+        Ok(self.session.get(url).await?.json::<Submissions>().await?)
     }
 
     #[maybe_async::maybe_async]
-    async fn get_comment_feed(
+    async fn get_comment_feed<L: DeserializeOwned>(
         &self,
         ty: &str,
         depth: Option<u32>,
         limit: Option<u32>,
-    ) -> Result<Comments, RouxError> {
+    ) -> Result<L, RouxError> {
         let url = &mut format!("{}/{}.json?", self.url, ty);
 
         if let Some(depth) = depth {
@@ -214,27 +295,20 @@ impl Subreddit {
         // but for posts the comments are in an array with the ONLY item
         // being same hash map as the one for subreddits...
         if url.contains("comments/") {
-            let mut comments = self
-                .client
-                .get(&url.to_owned())
-                .send()
+            let (_, comments) = self
+                .session
+                .get(url)
                 .await?
-                .json::<Vec<Comments>>()
+                .json::<(IgnoredAny, L)>()
                 .await?;
 
-            Ok(comments.pop().unwrap())
+            Ok(comments)
         } else {
-            Ok(self
-                .client
-                .get(&url.to_owned())
-                .send()
-                .await?
-                .json::<Comments>()
-                .await?)
+            Ok(self.session.get(url).await?.json::<L>().await?)
         }
     }
 
-    /// Get hot posts.
+    /// Get hot posts. `limit` is from 1 to 100, and a limit set in `options` replaces it.
     #[maybe_async::maybe_async]
     pub async fn hot(
         &self,
@@ -244,7 +318,7 @@ impl Subreddit {
         self.get_feed("hot", limit, options).await
     }
 
-    /// Get rising posts.
+    /// Get rising posts. `limit` is from 1 to 100, and a limit set in `options` replaces it.
     #[maybe_async::maybe_async]
     pub async fn rising(
         &self,
@@ -254,7 +328,7 @@ impl Subreddit {
         self.get_feed("rising", limit, options).await
     }
 
-    /// Get top posts.
+    /// Get top posts. `limit` is from 1 to 100, and a limit set in `options` replaces it.
     #[maybe_async::maybe_async]
     pub async fn top(
         &self,
@@ -264,7 +338,7 @@ impl Subreddit {
         self.get_feed("top", limit, options).await
     }
 
-    /// Get latest posts.
+    /// Get latest posts. `limit` is from 1 to 100, and a limit set in `options` replaces it.
     #[maybe_async::maybe_async]
     pub async fn latest(
         &self,
@@ -274,6 +348,13 @@ impl Subreddit {
         self.get_feed("new", limit, options).await
     }
 
+    /// Follow new posts as they are submitted, oldest first. See the
+    /// [`stream`](crate::util::stream) module for how polling works.
+    pub fn stream_submissions(&self, options: StreamOptions) -> LiveStream<'_, SubmissionData> {
+        let poller = Poller::new(options, |post: &SubmissionData| Some(post.name.to_owned()));
+        LiveStream::new(poller, move || self.latest(100, None))
+    }
+
     /// Get latest comments.
     #[maybe_async::maybe_async]
     pub async fn latest_comments(
@@ -284,6 +365,16 @@ impl Subreddit {
         self.get_comment_feed("comments", depth, limit).await
     }
 
+    /// Follow new comments as they are posted, oldest first. See the
+    /// [`stream`](crate::util::stream) module for how polling works.
+    ///
+    /// To follow several subreddits at once, combine them with `+`, e.g.
+    /// `Subreddit::new("rust+golang")`.
+    pub fn stream_comments(&self, options: StreamOptions) -> LiveStream<'_, CommentData> {
+        let poller = Poller::new(options, |comment: &CommentData| comment.name.to_owned());
+        LiveStream::new(poller, move || self.latest_comments(None, Some(100)))
+    }
+
     /// Get comments from article.
     #[maybe_async::maybe_async]
     pub async fn article_comments(
@@ -295,6 +386,96 @@ impl Subreddit {
         self.get_comment_feed(&format!("comments/{}", article), depth, limit)
             .await
     }
+
+    /// Get a post with its comments.
+    #[maybe_async::maybe_async]
+    pub async fn article(
+        &self,
+        article: &str,
+        options: Option<ArticleOption>,
+    ) -> Result<Article, RouxError> {
+        let url = &mut format!("{}/comments/{}.json?", self.url, article);
+
+        if let Some(options) = options {
+            options.build_url(url)?;
+        }
+
+        Ok(self.session.get(url).await?.json::<Article>().await?)
+    }
+
+    /// Get the whole comment thread of an article, with every comment Reddit left out
+    /// expanded. See the [`tree`](crate::comment::tree) module.
+    ///
+    /// This takes a request for every 100 left out comments, and one for every "continue
+    /// this thread" link, so it can be slow for large threads.
+    #[maybe_async::maybe_async]
+    pub async fn comment_tree(&self, article: &str) -> Result<CommentTree, RouxError> {
+        let mut tree = CommentTree::new();
+        let comments: Things = self
+            .get_comment_feed(&format!("comments/{}", article), None, None)
+            .await?;
+        let mut more = tree.insert(comments.data.children);
+
+        // Reddit can list a comment in more than one stub, and a sub-thread can end in the
+        // same link again, so nothing is fetched twice.
+        let mut requested = HashSet::new();
+
+        while !more.is_empty() {
+            let mut ids = Vec::new();
+            let mut threads = Vec::new();
+
+            for stub in more.drain(..) {
+                if stub.children.is_empty() {
+                    if requested.insert(stub.parent_id.to_owned()) {
+                        threads.push(stub.parent_id);
+                    }
+                } else {
+                    ids.extend(
+                        stub.children
+                            .into_iter()
+                            .filter(|id| requested.insert(id.to_owned())),
+                    );
+                }
+            }
+
+            // `/api/morechildren` only takes one request at a time.
+            for batch in ids.chunks(MORE_CHILDREN_LEN) {
+                more.extend(tree.insert(self.more_children(article, batch).await?));
+            }
+
+            for parent_id in threads {
+                let comment = parent_id.trim_start_matches("t1_");
+                let thread: Things = self
+                    .get_comment_feed(&format!("comments/{}/_/{}", article, comment), None, None)
+                    .await?;
+                more.extend(tree.insert(thread.data.children));
+            }
+        }
+
+        Ok(tree)
+    }
+
+    /// Get the comments with `ids` that were left out of an article's listing.
+    #[maybe_async::maybe_async]
+    async fn more_children(&self, article: &str, ids: &[String]) -> Result<Vec<Thing>, RouxError> {
+        let url = format!(
+            "{}/api/morechildren.json?api_type=json&link_id=t3_{}&children={}&limit_children=false",
+            self.base_url,
+            article,
+            ids.join(",")
+        );
+
+        let data = self
+            .session
+            .get(&url)
+            .await?
+            .json::<ApiResponse<MoreChildrenData>>()
+            .await?
+            .into_result()
+            .map_err(RouxError::Api)?;
+
+        Ok(data.map(|data| data.things).unwrap_or_default())
+    }
 }
 
 #[cfg(test)]
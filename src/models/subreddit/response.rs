@@ -1,5 +1,5 @@
 //! # Subreddit Responses
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::models::response::BasicListing;
 
@@ -13,7 +13,7 @@ pub struct SubredditResponse {
 }
 
 /// accounts_active and active_user_count fields in `SubredditData`.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum AccountsActive {
     /// The (approximate) number of users interacting with this subreddit over the past 15 minutes.
@@ -26,7 +26,7 @@ pub enum AccountsActive {
 /// If the API user has user flair in this subreddit, and its user_flair_type is richtext,
 /// this will be an array containing two string elements which define the user's flair.
 #[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct RichtextFlair {
     /// contains the string text
     e: String,
@@ -35,7 +35,7 @@ pub struct RichtextFlair {
 }
 
 /// SubredditData
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SubredditData {
     /// The HTML hex code of the API user's flair background color in this subreddit,
     /// if any. If the API user has no flair, or no background color is defined, this will be null.
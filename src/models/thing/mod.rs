@@ -0,0 +1,166 @@
+//! # Things
+//! Listings like saved items, a user's overview or the inbox mix several kinds of things.
+//! [`Thing`] decodes each one by its `kind`, so a malformed comment is reported as a comment
+//! instead of as data that matched nothing.
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::models::about::AboutData;
+use crate::models::comment::CommentData;
+use crate::models::inbox::InboxData;
+use crate::models::response::{BasicThing, Listing};
+use crate::models::submission::SubmissionData;
+use crate::models::subreddit::response::SubredditData;
+use crate::util::Kind;
+
+/// The kinds a [`Thing`] can have.
+const KINDS: &[&str] = &["t1", "t2", "t3", "t4", "t5", "more"];
+
+/// A thing of any kind.
+// Most listings hold a single kind, so boxing would only add an allocation per item.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum Thing {
+    /// Comment (`t1`)
+    Comment(CommentData),
+    /// Account (`t2`)
+    Account(AboutData),
+    /// Post (`t3`)
+    Submission(SubmissionData),
+    /// Private message (`t4`)
+    Message(InboxData),
+    /// Subreddit (`t5`)
+    Subreddit(SubredditData),
+    /// Comments that were left out of a comment tree (`more`)
+    More(MoreData),
+}
+
+impl Thing {
+    /// The kind of thing, or `None` for [`Thing::More`].
+    pub fn kind(&self) -> Option<Kind> {
+        match self {
+            Thing::Comment(_) => Some(Kind::Comment),
+            Thing::Account(_) => Some(Kind::Account),
+            Thing::Submission(_) => Some(Kind::Link),
+            Thing::Message(_) => Some(Kind::Message),
+            Thing::Subreddit(_) => Some(Kind::Subreddit),
+            Thing::More(_) => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Thing {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Thing, D::Error> {
+        let thing = BasicThing::<Value>::deserialize(deserializer)?;
+        let kind = thing.kind.ok_or_else(|| de::Error::missing_field("kind"))?;
+
+        match kind.as_str() {
+            "t1" => data(&kind, thing.data).map(Thing::Comment),
+            "t2" => data(&kind, thing.data).map(Thing::Account),
+            "t3" => data(&kind, thing.data).map(Thing::Submission),
+            "t4" => data(&kind, thing.data).map(Thing::Message),
+            "t5" => data(&kind, thing.data).map(Thing::Subreddit),
+            "more" => data(&kind, thing.data).map(Thing::More),
+            _ => Err(de::Error::unknown_variant(&kind, KINDS)),
+        }
+    }
+}
+
+impl Serialize for Thing {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Thing::Comment(data) => tagged("t1", data, serializer),
+            Thing::Account(data) => tagged("t2", data, serializer),
+            Thing::Submission(data) => tagged("t3", data, serializer),
+            Thing::Message(data) => tagged("t4", data, serializer),
+            Thing::Subreddit(data) => tagged("t5", data, serializer),
+            Thing::More(data) => tagged("more", data, serializer),
+        }
+    }
+}
+
+/// Encodes `data` with its `kind`, the way Reddit sends it.
+fn tagged<T: Serialize, S: Serializer>(
+    kind: &str,
+    data: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    BasicThing {
+        kind: Some(kind.to_owned()),
+        data,
+    }
+    .serialize(serializer)
+}
+
+/// Decodes the `data` of a thing of `kind`, naming the kind in any error.
+fn data<T: DeserializeOwned, E: de::Error>(kind: &str, data: Value) -> Result<T, E> {
+    serde_json::from_value(data).map_err(|e| E::custom(format!("invalid `{}` thing: {}", kind, e)))
+}
+
+/// MoreData
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoreData {
+    /// The number of comments left out, including replies to them
+    pub count: u32,
+    /// Name
+    pub name: String,
+    /// ID
+    pub id: String,
+    /// The fullname of the comment or post the left out comments reply to
+    pub parent_id: String,
+    /// Depth
+    #[serde(default)]
+    pub depth: u32,
+    /// The ids of the left out comments
+    pub children: Vec<String>,
+}
+
+/// A listing of things of any kind.
+pub type Things = BasicThing<Listing<Thing>>;
+
+#[cfg(test)]
+mod tests {
+    use super::{Thing, Things};
+
+    #[test]
+    fn test_deserialize() {
+        let things: Things = serde_json::from_str(
+            r#"{"kind": "Listing", "data": {"after": null, "before": null, "children": [
+                {"kind": "t1", "data": {"id": "c1", "name": "t1_c1", "body": "Hi"}},
+                {"kind": "t4", "data": {"id": "m1", "name": "t4_m1", "subject": "Hello",
+                    "was_comment": false, "author": "spez", "parent_id": null,
+                    "subreddit_name_prefixed": null, "new": true, "type": "unknown",
+                    "body": "Hi", "dest": "roux", "body_html": "", "created": 0.0,
+                    "created_utc": 0.0, "context": ""}},
+                {"kind": "more", "data": {"count": 2, "name": "t1_c2", "id": "c2",
+                    "parent_id": "t3_a", "depth": 0, "children": ["c2", "c3"]}}
+            ]}}"#,
+        )
+        .unwrap();
+
+        let children = things.data.children;
+        assert!(matches!(children[0], Thing::Comment(ref c) if c.body.as_deref() == Some("Hi")));
+        assert!(matches!(children[1], Thing::Message(ref m) if m.subject == "Hello"));
+        assert!(matches!(children[2], Thing::More(ref m) if m.children == ["c2", "c3"]));
+
+        // Things are encoded the way they were decoded, kind and all.
+        let json = serde_json::to_value(&children[2]).unwrap();
+        assert_eq!(json["kind"], "more");
+        let more: Thing = serde_json::from_value(json).unwrap();
+        assert!(matches!(more, Thing::More(ref m) if m.count == 2));
+    }
+
+    #[test]
+    fn test_deserialize_error() {
+        let error = serde_json::from_str::<Thing>(r#"{"kind": "t4", "data": {"id": "m1"}}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("invalid `t4` thing: missing field"));
+
+        let error = serde_json::from_str::<Thing>(r#"{"kind": "t9", "data": {}}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown variant `t9`"));
+    }
+}
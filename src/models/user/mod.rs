@@ -28,8 +28,9 @@
 extern crate serde_json;
 
 use crate::client::Client;
+use crate::session::Session;
 use crate::util::defaults::default_client;
-use crate::util::{FeedOption, RouxError};
+use crate::util::{url, FeedOption, RateLimit, RetryPolicy, RouxError};
 
 use crate::models::{About, Comments, Overview, Submissions};
 
@@ -37,7 +38,8 @@ use crate::models::{About, Comments, Overview, Submissions};
 pub struct User {
     /// User's name.
     pub user: String,
-    client: Client,
+    url: String,
+    session: Session,
 }
 
 impl User {
@@ -45,80 +47,90 @@ impl User {
     pub fn new(user: &str) -> User {
         User {
             user: user.to_owned(),
-            client: default_client(),
+            url: format!("{}/user/{}", url::BASE_URL, user),
+            session: Session::new(&default_client()),
         }
     }
 
+    /// Create a new authenticated `User` instance using an oauth client
+    /// from the `Reddit` module.
+    pub fn new_oauth(user: &str, client: &Client) -> User {
+        User::from_session(user, Session::new(client))
+    }
+
+    /// Create a new authenticated `User` instance that shares `session`'s token.
+    pub(crate) fn from_session(user: &str, session: Session) -> User {
+        User {
+            user: user.to_owned(),
+            url: format!("{}/user/{}", url::OAUTH_URL, user),
+            session,
+        }
+    }
+
+    /// Sends requests to `base_url` instead of Reddit, e.g. a local server standing in for
+    /// it in tests.
+    pub fn base_url(mut self, base_url: &str) -> User {
+        self.url = format!("{}/user/{}", base_url.trim_end_matches('/'), self.user);
+        self
+    }
+
+    /// Sets how requests that fail with a server error or a network error are retried.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> User {
+        self.session = self.session.with_retry_policy(policy);
+        self
+    }
+
+    /// The request budget Reddit last reported for this user's requests.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.session.rate_limit()
+    }
+
     /// Get user's overview.
     #[maybe_async::maybe_async]
     pub async fn overview(&self, options: Option<FeedOption>) -> Result<Overview, RouxError> {
-        let url = &mut format!("https://www.reddit.com/user/{}/overview/.json?", self.user);
+        let url = &mut format!("{}/overview/.json?", self.url);
 
         if let Some(options) = options {
-            options.build_url(url);
+            options.build_url(url)?;
         }
 
-        Ok(self
-            .client
-            .get(&url.to_owned())
-            .send()
-            .await?
-            .json::<Overview>()
-            .await?)
+        Ok(self.session.get(url).await?.json::<Overview>().await?)
     }
 
     /// Get user's submitted posts.
     #[maybe_async::maybe_async]
     pub async fn submitted(&self, options: Option<FeedOption>) -> Result<Submissions, RouxError> {
-        let url = &mut format!("https://www.reddit.com/user/{}/submitted/.json?", self.user);
+        let url = &mut format!("{}/submitted/.json?", self.url);
 
         if let Some(options) = options {
-            options.build_url(url);
+            options.build_url(url)?;
         }
 
-        Ok(self
-            .client
-            .get(&url.to_owned())
-            .send()
-            .await?
-            .json::<Submissions>()
-            .await?)
+        Ok(self.session.get(url).await?.json::<Submissions>().await?)
     }
 
     /// Get user's submitted comments.
     #[maybe_async::maybe_async]
     pub async fn comments(&self, options: Option<FeedOption>) -> Result<Comments, RouxError> {
-        let url = &mut format!("https://www.reddit.com/user/{}/comments/.json?", self.user);
+        let url = &mut format!("{}/comments/.json?", self.url);
 
         if let Some(options) = options {
-            options.build_url(url);
+            options.build_url(url)?;
         }
 
-        Ok(self
-            .client
-            .get(&url.to_owned())
-            .send()
-            .await?
-            .json::<Comments>()
-            .await?)
+        Ok(self.session.get(url).await?.json::<Comments>().await?)
     }
 
     /// Get user's about page
     #[maybe_async::maybe_async]
     pub async fn about(&self, options: Option<FeedOption>) -> Result<About, RouxError> {
-        let url = &mut format!("https://www.reddit.com/user/{}/about/.json?", self.user);
+        let url = &mut format!("{}/about/.json?", self.url);
 
         if let Some(options) = options {
-            options.build_url(url);
+            options.build_url(url)?;
         }
 
-        Ok(self
-            .client
-            .get(&url.to_owned())
-            .send()
-            .await?
-            .json::<About>()
-            .await?)
+        Ok(self.session.get(url).await?.json::<About>().await?)
     }
 }
 
@@ -0,0 +1,293 @@
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::auth::{self, Grant, Token};
+use crate::client::{self, Client, RequestBuilder, Response};
+use crate::config::Config;
+use crate::util::retry::is_transient;
+use crate::util::{RateLimit, RateLimiter, RetryPolicy, RouxError};
+
+/// Everything a handle needs to make requests. Clones share the same token, so a refresh
+/// made through one handle is seen by every other handle created from the same login.
+#[derive(Debug, Clone)]
+pub struct Session {
+    client: Client,
+    limiter: RateLimiter,
+    retry: RetryPolicy,
+    auth: Option<Arc<Mutex<Auth>>>,
+}
+
+#[derive(Debug)]
+struct Auth {
+    config: Config,
+    grant: Option<Grant>,
+    token: Token,
+}
+
+impl Session {
+    /// A session that sends requests with `client` as is.
+    pub fn new(client: &Client) -> Session {
+        Session {
+            client: client.to_owned(),
+            limiter: RateLimiter::new(),
+            retry: RetryPolicy::default(),
+            auth: None,
+        }
+    }
+
+    /// A session that keeps `token` fresh, renewing it with `grant` when it runs out.
+    pub fn authenticated(
+        config: &Config,
+        client: &Client,
+        grant: Option<Grant>,
+        token: Token,
+    ) -> Session {
+        let mut config = config.to_owned();
+        token.apply(&mut config);
+
+        Session {
+            client: client.to_owned(),
+            limiter: config.rate_limiter.to_owned(),
+            retry: config.retry_policy,
+            auth: Some(Arc::new(Mutex::new(Auth {
+                config,
+                grant,
+                token,
+            }))),
+        }
+    }
+
+    /// Retry failed requests according to `policy` instead of the config's policy.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Session {
+        self.retry = policy;
+        self
+    }
+
+    /// The underlying client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// The request budget Reddit last reported.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.limiter.current()
+    }
+
+    /// The latest config, including the current tokens and scopes.
+    pub fn config(&self) -> Option<Config> {
+        self.auth
+            .as_ref()
+            .map(|auth| auth.lock().unwrap().config.to_owned())
+    }
+
+    /// The current access token.
+    pub fn access_token(&self) -> Option<String> {
+        self.auth
+            .as_ref()
+            .map(|auth| auth.lock().unwrap().token.access_token.to_owned())
+    }
+
+    /// The current refresh token, if one was issued.
+    pub fn refresh_token(&self) -> Option<String> {
+        self.auth
+            .as_ref()
+            .and_then(|auth| auth.lock().unwrap().config.refresh_token.to_owned())
+    }
+
+    /// Record the username of a login that did not know it, and save the token now that it
+    /// has a key in the token store.
+    pub fn identify(&self, username: &str) -> Result<(), RouxError> {
+        let (config, grant, token) = match &self.auth {
+            Some(auth) => {
+                let mut auth = auth.lock().unwrap();
+                auth.config.username = Some(username.to_owned());
+                match &auth.grant {
+                    Some(grant) => (
+                        auth.config.to_owned(),
+                        grant.to_owned(),
+                        auth.token.to_owned(),
+                    ),
+                    None => return Ok(()),
+                }
+            }
+            None => return Ok(()),
+        };
+
+        auth::save_token(&config, &grant, &token).map_err(|e| RouxError::TokenStore(Arc::new(e)))
+    }
+
+    /// Get the current access token, renewing it first if it is about to expire.
+    #[maybe_async::maybe_async]
+    async fn bearer(&self) -> Result<Option<String>, RouxError> {
+        let (expires_soon, access_token) = match &self.auth {
+            Some(auth) => {
+                let auth = auth.lock().unwrap();
+                (
+                    auth.token.expires_soon() && auth.grant.is_some(),
+                    auth.token.access_token.to_owned(),
+                )
+            }
+            None => return Ok(None),
+        };
+
+        if expires_soon {
+            Ok(self.refresh().await?)
+        } else {
+            Ok(Some(access_token))
+        }
+    }
+
+    /// Get a new access token, if this session knows how to.
+    #[maybe_async::maybe_async]
+    async fn refresh(&self) -> Result<Option<String>, RouxError> {
+        let auth = match &self.auth {
+            Some(auth) => auth,
+            None => return Ok(None),
+        };
+
+        let (config, grant) = {
+            let auth = auth.lock().unwrap();
+            match auth
+                .grant
+                .as_ref()
+                .and_then(|grant| grant.renewal(&auth.config))
+            {
+                Some(grant) => (auth.config.to_owned(), grant),
+                None => return Ok(None),
+            }
+        };
+
+        // The lock is not held across the request, so concurrent refreshes can happen. They
+        // all get valid tokens and the last one written wins.
+        let token = auth::request_token(&self.client, &config, &grant).await?;
+
+        let config = {
+            let mut auth = auth.lock().unwrap();
+            token.apply(&mut auth.config);
+            auth.grant = Some(grant.to_owned());
+            auth.token = token.to_owned();
+            auth.config.to_owned()
+        };
+
+        auth::save_token(&config, &grant, &token)
+            .map_err(|e| RouxError::TokenStore(Arc::new(e)))?;
+
+        Ok(Some(token.access_token))
+    }
+
+    /// Send a request built by `build`, retrying transient failures as the retry policy
+    /// allows. Requests that are not `idempotent` are only retried if the policy says so.
+    #[maybe_async::maybe_async]
+    pub async fn send<F>(&self, idempotent: bool, build: F) -> Result<Response, RouxError>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        let mut attempt = 1;
+
+        loop {
+            let result = self.send_once(&build).await;
+
+            let retry = match &result {
+                Ok(response) if is_transient(response.status().as_u16()) => {
+                    Some(Some(response.headers()))
+                }
+                Err(RouxError::Network(e))
+                    if e.is_connect() || e.is_timeout() || e.is_request() =>
+                {
+                    Some(None)
+                }
+                _ => None,
+            };
+
+            match retry {
+                Some(headers) if self.retry.allows(attempt, idempotent) => {
+                    let delay = self.retry.delay(attempt, headers);
+                    drop(result);
+                    client::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => return check_status(result?).await,
+            }
+        }
+    }
+
+    /// Send a request built by `build` once. If Reddit rejects the token it is renewed and
+    /// the request is sent once more.
+    #[maybe_async::maybe_async]
+    async fn send_once<F>(&self, build: &F) -> Result<Response, RouxError>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        let bearer = self.bearer().await?;
+        let response = self
+            .execute(authorize(build(&self.client), &bearer))
+            .await?;
+
+        if response.status() == 401 && bearer.is_some() {
+            if let Some(bearer) = self.refresh().await? {
+                return self
+                    .execute(authorize(build(&self.client), &Some(bearer)))
+                    .await;
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Send `request` once the rate limit allows it, and record the budget Reddit reports back.
+    #[maybe_async::maybe_async]
+    async fn execute(&self, request: RequestBuilder) -> Result<Response, RouxError> {
+        if let Some(wait) = self.limiter.acquire() {
+            client::sleep(wait).await;
+        }
+
+        let response = request.send().await?;
+        self.limiter.update(response.headers());
+
+        Ok(response)
+    }
+
+    /// Send a GET request to `url`.
+    #[maybe_async::maybe_async]
+    pub async fn get(&self, url: &str) -> Result<Response, RouxError> {
+        self.send(true, |client| client.get(url)).await
+    }
+
+    /// Send a POST request to `url` with a form body.
+    #[maybe_async::maybe_async]
+    pub async fn post<T: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        form: &T,
+    ) -> Result<Response, RouxError> {
+        self.send(false, |client| client.post(url).form(form)).await
+    }
+
+    /// Send a POST request to `url` with a JSON body.
+    #[maybe_async::maybe_async]
+    pub async fn post_json<T: Serialize + ?Sized>(
+        &self,
+        url: &str,
+        json: &T,
+    ) -> Result<Response, RouxError> {
+        self.send(false, |client| client.post(url).json(json)).await
+    }
+}
+
+/// Turn non-success responses into errors.
+#[maybe_async::maybe_async]
+async fn check_status(response: Response) -> Result<Response, RouxError> {
+    if response.status().is_success() {
+        Ok(response)
+    } else {
+        Err(RouxError::from_response(response).await)
+    }
+}
+
+fn authorize(request: RequestBuilder, bearer: &Option<String>) -> RequestBuilder {
+    match bearer {
+        Some(bearer) => request.bearer_auth(bearer),
+        None => request,
+    }
+}
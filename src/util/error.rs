@@ -1,54 +1,282 @@
 use std::error;
 use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
 
+use serde::Deserialize;
 use serde_json;
 
 use crate::client;
+use crate::util::retry;
+
+/// How much of an error response's body is kept in an [`HttpError`].
+const BODY_SNIPPET_LEN: usize = 512;
 
 /// Error type that occurs when an API request fails for some reason.
-#[derive(Debug)]
+///
+/// Errors from other crates are held in an [`Arc`], so the error can be cloned, e.g. to
+/// hand it to several waiting tasks.
+#[derive(Debug, Clone)]
 pub enum RouxError {
-    /// Occurs when the API has returned a non-success error code.
-    Status(client::Response),
+    /// Occurs when Reddit rejected the access token, even after it was renewed.
+    Unauthorized(HttpError),
+    /// Occurs when the request is not allowed, e.g. for a private or quarantined subreddit.
+    /// Reddit usually says why in [`HttpError::reason`].
+    Forbidden(HttpError),
+    /// Occurs when the thing asked for does not exist, e.g. a deleted user or a banned
+    /// subreddit.
+    NotFound(HttpError),
+    /// Occurs when Reddit answered `429 Too Many Requests`, with how long until the rate limit
+    /// resets if Reddit said.
+    RateLimited(HttpError, Option<Duration>),
+    /// Occurs when Reddit failed with a `5xx` status, after any retries.
+    ServerError(HttpError),
+    /// Occurs when the API has returned any other non-success status code.
+    Status(HttpError),
     /// Occurs if the HTTP response from Reddit was corrupt and
     /// reqwest could not parse it.
-    Network(client::Error),
+    Network(Arc<client::Error>),
     /// Occurs if serde could not Deserialize the response.
-    Parse(serde_json::Error),
+    Parse(Arc<serde_json::Error>),
     /// Occurs if there is a grant error.
     Auth(String),
-    /// Occurs if [`Reddit::create_client`] is called before [`Reddit::username`] and [`Reddit::password`].
+    /// Occurs if the credentials a grant needs have not been set, e.g.
+    /// [`Reddit::login`](crate::Reddit::login) without
+    /// [`Reddit::username`](crate::Reddit::username) and
+    /// [`Reddit::password`](crate::Reddit::password) or a
+    /// [`Reddit::refresh_token`](crate::Reddit::refresh_token), or
+    /// [`Reddit::authorize`](crate::Reddit::authorize) without a
+    /// [`Reddit::redirect_uri`](crate::Reddit::redirect_uri).
     CredentialsNotSet,
     /// Occurs if endpoint requires OAuth
     OAuthClientRequired,
+    /// Occurs if Reddit rejects a password login for a missing or invalid one-time code. Reddit
+    /// gives the same error for a wrong password, so this is returned for any rejected login
+    /// that sent a code. Also occurs if the TOTP secret is not valid base32.
+    TwoFactor(String),
+    /// Occurs if a [`TokenStore`](crate::util::TokenStore) could not load or save a token.
+    TokenStore(Arc<io::Error>),
+    /// Occurs if Reddit accepted the request but refused to act on it, e.g. `SUBREDDIT_NOEXIST`
+    /// or `RATELIMIT`. Reddit can report several errors at once.
+    Api(Vec<RedditApiError>),
+    /// Occurs if a crawl is resumed from a [`Cursor`](crate::util::paginator::Cursor) whose
+    /// anchor Reddit no longer honours, e.g. because the item was deleted or has fallen out of
+    /// the listing. Holds the anchor.
+    StaleCursor(String),
+    /// Occurs if a [`FeedOption`](crate::util::FeedOption) cannot be sent, e.g. because it
+    /// has both `after` and `before` or a `limit` over 100.
+    InvalidOption(String),
+    /// Occurs if a string is not a valid [`Fullname`](crate::util::Fullname), e.g. an id
+    /// without its `t3_` prefix. Holds the string.
+    InvalidFullname(String),
+    /// Occurs if a file cannot be uploaded as [`Media`](crate::me::media::Media), e.g.
+    /// because it is not a PNG, JPEG, GIF, MP4 or MOV file, or its contents are not what
+    /// its extension says.
+    InvalidMedia(String),
+    /// Occurs if Reddit accepted an image or video post but did not create it while roux
+    /// waited, e.g. because a long video is still processing. Holds the title; the post may
+    /// still appear later.
+    MediaProcessing(String),
+}
+
+impl RouxError {
+    /// The failed response, for errors caused by a non-success status code.
+    pub fn http(&self) -> Option<&HttpError> {
+        match *self {
+            RouxError::Unauthorized(ref err)
+            | RouxError::Forbidden(ref err)
+            | RouxError::NotFound(ref err)
+            | RouxError::RateLimited(ref err, _)
+            | RouxError::ServerError(ref err)
+            | RouxError::Status(ref err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Turn a non-success response into the matching error, reading its body.
+    #[maybe_async::maybe_async]
+    pub(crate) async fn from_response(response: client::Response) -> RouxError {
+        let status = response.status().as_u16();
+        let url = response.url().to_string();
+        let reset = retry::retry_after(response.headers())
+            .or_else(|| retry::ratelimit_reset(response.headers()));
+        let body = response.text().await.unwrap_or_default();
+
+        let error = HttpError {
+            status,
+            url,
+            reason: serde_json::from_str::<ErrorBody>(&body)
+                .ok()
+                .and_then(|body| body.reason),
+            body: snippet(body),
+        };
+
+        match status {
+            401 => RouxError::Unauthorized(error),
+            403 => RouxError::Forbidden(error),
+            404 => RouxError::NotFound(error),
+            429 => RouxError::RateLimited(error, reset),
+            500..=599 => RouxError::ServerError(error),
+            _ => RouxError::Status(error),
+        }
+    }
+}
+
+/// A response with a non-success status code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpError {
+    /// The status code.
+    pub status: u16,
+    /// The url that was requested.
+    pub url: String,
+    /// Why Reddit refused, if it said, e.g. `private`, `banned` or `quarantined`.
+    pub reason: Option<String>,
+    /// The start of the response body.
+    pub body: String,
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} for {}", self.status, self.url)?;
+        if let Some(ref reason) = self.reason {
+            write!(f, " ({})", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// The error bodies Reddit sends, e.g. `{"reason": "private", "message": "Forbidden",
+/// "error": 403}`.
+#[derive(Deserialize)]
+struct ErrorBody {
+    reason: Option<String>,
+}
+
+fn snippet(mut body: String) -> String {
+    if body.len() > BODY_SNIPPET_LEN {
+        let mut end = BODY_SNIPPET_LEN;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+    }
+    body
+}
+
+/// An error Reddit reported in the `json.errors` array of an `api_type=json` response.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "(String, String, Option<String>)")]
+pub struct RedditApiError {
+    /// The error code, e.g. `RATELIMIT` or `TOO_LONG`.
+    pub code: String,
+    /// The human readable message.
+    pub message: String,
+    /// The form field the error is about, if any.
+    pub field: Option<String>,
+}
+
+impl From<(String, String, Option<String>)> for RedditApiError {
+    fn from((code, message, field): (String, String, Option<String>)) -> Self {
+        RedditApiError {
+            code,
+            message,
+            field: field.filter(|field| !field.is_empty()),
+        }
+    }
+}
+
+impl RedditApiError {
+    /// How long to wait before trying again, for `RATELIMIT` errors. Reddit only says so in
+    /// the message, e.g. "you are doing that too much. try again in 9 minutes."
+    pub fn retry_after(&self) -> Option<Duration> {
+        if self.code != "RATELIMIT" {
+            return None;
+        }
+
+        let words: Vec<&str> = self.message.split_whitespace().collect();
+        words.windows(2).find_map(|pair| {
+            let amount = pair[0].parse::<u64>().ok()?;
+            let unit = pair[1].trim_end_matches(|c: char| !c.is_alphabetic());
+
+            match unit.trim_end_matches('s') {
+                "millisecond" => Some(Duration::from_millis(amount)),
+                "second" => Some(Duration::from_secs(amount)),
+                "minute" => Some(Duration::from_secs(amount * 60)),
+                "hour" => Some(Duration::from_secs(amount * 3600)),
+                _ => None,
+            }
+        })
+    }
+}
+
+impl fmt::Display for RedditApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.field {
+            Some(ref field) => write!(f, "{}: {} (field {})", self.code, self.message, field),
+            None => write!(f, "{}: {}", self.code, self.message),
+        }
+    }
 }
 
 impl From<client::Error> for RouxError {
     fn from(e: client::Error) -> Self {
-        RouxError::Network(e)
+        RouxError::Network(Arc::new(e))
     }
 }
 
 impl From<serde_json::Error> for RouxError {
     fn from(e: serde_json::Error) -> Self {
-        RouxError::Parse(e)
+        RouxError::Parse(Arc::new(e))
     }
 }
 
 impl fmt::Display for RouxError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            RouxError::Status(ref err) => write!(f, "Status error: {}", err.status()),
+            RouxError::Unauthorized(ref err) => write!(f, "Unauthorized: {}", err),
+            RouxError::Forbidden(ref err) => write!(f, "Forbidden: {}", err),
+            RouxError::NotFound(ref err) => write!(f, "Not found: {}", err),
+            RouxError::RateLimited(ref err, Some(reset)) => write!(
+                f,
+                "Rate limited: {}, resets in {}s",
+                err,
+                reset.as_secs()
+            ),
+            RouxError::RateLimited(ref err, None) => write!(f, "Rate limited: {}", err),
+            RouxError::ServerError(ref err) => write!(f, "Server error: {}", err),
+            RouxError::Status(ref err) => write!(f, "Status error: {}", err),
             RouxError::Network(ref err) => err.fmt(f),
             RouxError::Parse(ref err) => err.fmt(f),
             RouxError::Auth(ref err) => write!(f, "Auth error: {}", err),
             RouxError::CredentialsNotSet => write!(
                 f,
-                "Must set username and password before calling create_client"
+                "Must set credentials (username and password, refresh token or redirect uri) before authenticating"
             ),
             RouxError::OAuthClientRequired => {
                 write!(f, "Endpoint requires authentication with OAuth")
             }
+            RouxError::TwoFactor(ref err) => write!(f, "Two-factor auth error: {}", err),
+            RouxError::TokenStore(ref err) => write!(f, "Token store error: {}", err),
+            RouxError::StaleCursor(ref anchor) => {
+                write!(f, "Cannot resume listing after {}, Reddit no longer lists it", anchor)
+            }
+            RouxError::InvalidOption(ref err) => write!(f, "Invalid option: {}", err),
+            RouxError::InvalidFullname(ref s) => write!(f, "Invalid fullname: {}", s),
+            RouxError::InvalidMedia(ref err) => write!(f, "Invalid media: {}", err),
+            RouxError::MediaProcessing(ref title) => {
+                write!(f, "Reddit has not created the post {:?} yet", title)
+            }
+            RouxError::Api(ref errors) => {
+                write!(f, "API error: ")?;
+                for (i, err) in errors.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    err.fmt(f)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -56,12 +284,88 @@ impl fmt::Display for RouxError {
 impl error::Error for RouxError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
+            RouxError::Unauthorized(_) => None,
+            RouxError::Forbidden(_) => None,
+            RouxError::NotFound(_) => None,
+            RouxError::RateLimited(_, _) => None,
+            RouxError::ServerError(_) => None,
             RouxError::Status(_) => None,
             RouxError::Auth(_) => None,
-            RouxError::Network(ref err) => Some(err),
-            RouxError::Parse(ref err) => Some(err),
+            RouxError::Network(ref err) => Some(err.as_ref()),
+            RouxError::Parse(ref err) => Some(err.as_ref()),
             RouxError::CredentialsNotSet => None,
             RouxError::OAuthClientRequired => None,
+            RouxError::TwoFactor(_) => None,
+            RouxError::TokenStore(ref err) => Some(err.as_ref()),
+            RouxError::Api(_) => None,
+            RouxError::StaleCursor(_) => None,
+            RouxError::InvalidOption(_) => None,
+            RouxError::InvalidFullname(_) => None,
+            RouxError::InvalidMedia(_) => None,
+            RouxError::MediaProcessing(_) => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{snippet, RedditApiError, RouxError};
+
+    #[test]
+    fn test_deserialize() {
+        let errors: Vec<RedditApiError> = serde_json::from_str(
+            r#"[["SUBREDDIT_NOEXIST", "that subreddit doesn't exist", "sr"],
+                ["NO_TEXT", "we need something here", null]]"#,
+        )
+        .unwrap();
+
+        assert_eq!(errors[0].code, "SUBREDDIT_NOEXIST");
+        assert_eq!(errors[0].field.as_deref(), Some("sr"));
+        assert_eq!(errors[1].field, None);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let error = |code: &str, message: &str| RedditApiError {
+            code: code.to_owned(),
+            message: message.to_owned(),
+            field: Some("ratelimit".to_owned()),
+        };
+
+        assert_eq!(
+            error(
+                "RATELIMIT",
+                "you are doing that too much. try again in 9 minutes."
+            )
+            .retry_after(),
+            Some(Duration::from_secs(540))
+        );
+        assert_eq!(
+            error(
+                "RATELIMIT",
+                "Looks like you've been doing that a lot. Take a break for 1 second before trying again."
+            )
+            .retry_after(),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            error("TOO_LONG", "try again in 9 minutes").retry_after(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_clone() {
+        let error = RouxError::from(serde_json::from_str::<u32>("x").unwrap_err());
+        assert_eq!(error.clone().to_string(), error.to_string());
+    }
+
+    #[test]
+    fn test_snippet() {
+        assert_eq!(snippet("short".to_owned()), "short");
+        assert_eq!(snippet("é".repeat(300)).len(), 512);
+        assert_eq!(snippet("aé".repeat(200)).len(), 511);
+    }
+}
@@ -0,0 +1,221 @@
+//! # Fullnames
+//! Reddit identifies everything by a fullname: the kind of thing, an underscore and its
+//! base36 id, e.g. `t3_15bfi0` for a post. [`Fullname`] keeps the two apart, so an id
+//! cannot be sent where a fullname is needed.
+//!
+//! ```
+//! use roux::util::{Fullname, Kind};
+//!
+//! let post: Fullname = "t3_15bfi0".parse().unwrap();
+//! assert_eq!(post.kind(), Kind::Link);
+//! assert_eq!(post.id(), "15bfi0");
+//! assert_eq!(post.id_number(), 69_397_560);
+//!
+//! let comment = Fullname::new(Kind::Comment, "c0hjbop").unwrap();
+//! assert_eq!(comment.to_string(), "t1_c0hjbop");
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::RouxError;
+
+/// The kind of a thing, the `tN` part of its fullname.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// `t1`
+    Comment,
+    /// `t2`
+    Account,
+    /// `t3`, a post
+    Link,
+    /// `t4`
+    Message,
+    /// `t5`
+    Subreddit,
+    /// `t6`
+    Award,
+}
+
+impl Kind {
+    /// Gets the fullname prefix for the kind, without the underscore.
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Kind::Comment => "t1",
+            Kind::Account => "t2",
+            Kind::Link => "t3",
+            Kind::Message => "t4",
+            Kind::Subreddit => "t5",
+            Kind::Award => "t6",
+        }
+    }
+
+    /// The kind with `prefix`, e.g. `t3`.
+    pub fn from_prefix(prefix: &str) -> Option<Kind> {
+        match prefix {
+            "t1" => Some(Kind::Comment),
+            "t2" => Some(Kind::Account),
+            "t3" => Some(Kind::Link),
+            "t4" => Some(Kind::Message),
+            "t5" => Some(Kind::Subreddit),
+            "t6" => Some(Kind::Award),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.prefix())
+    }
+}
+
+/// The fullname of a thing, e.g. `t3_15bfi0`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Fullname {
+    kind: Kind,
+    id: String,
+}
+
+impl Fullname {
+    /// Create the fullname of the thing of `kind` with the base36 `id`, e.g. `15bfi0`.
+    pub fn new(kind: Kind, id: &str) -> Result<Fullname, RouxError> {
+        let valid = !id.is_empty()
+            && id
+                .bytes()
+                .all(|b| b.is_ascii_digit() || b.is_ascii_lowercase())
+            && u64::from_str_radix(id, 36).is_ok();
+
+        if !valid {
+            return Err(RouxError::InvalidFullname(format!("{}_{}", kind, id)));
+        }
+
+        Ok(Fullname {
+            kind,
+            id: id.to_owned(),
+        })
+    }
+
+    /// Create the fullname of the thing of `kind` whose id is `number` in base10.
+    pub fn from_id_number(kind: Kind, mut number: u64) -> Fullname {
+        let mut digits = Vec::new();
+        loop {
+            digits.push(std::char::from_digit((number % 36) as u32, 36).unwrap());
+            number /= 36;
+            if number == 0 {
+                break;
+            }
+        }
+
+        Fullname {
+            kind,
+            id: digits.into_iter().rev().collect(),
+        }
+    }
+
+    /// The kind of thing.
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// The base36 id, without the kind.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The id as a number.
+    pub fn id_number(&self) -> u64 {
+        // Checked when the fullname was created.
+        u64::from_str_radix(&self.id, 36).unwrap()
+    }
+}
+
+/// Joins `fullnames` with commas, the way endpoints taking several ids expect them.
+pub(crate) fn join(fullnames: &[Fullname]) -> String {
+    fullnames
+        .iter()
+        .map(Fullname::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl FromStr for Fullname {
+    type Err = RouxError;
+
+    fn from_str(s: &str) -> Result<Fullname, RouxError> {
+        let (prefix, id) = s
+            .split_once('_')
+            .ok_or_else(|| RouxError::InvalidFullname(s.to_owned()))?;
+        let kind =
+            Kind::from_prefix(prefix).ok_or_else(|| RouxError::InvalidFullname(s.to_owned()))?;
+
+        Fullname::new(kind, id)
+    }
+}
+
+impl TryFrom<String> for Fullname {
+    type Error = RouxError;
+
+    fn try_from(s: String) -> Result<Fullname, RouxError> {
+        s.parse()
+    }
+}
+
+impl From<Fullname> for String {
+    fn from(fullname: Fullname) -> String {
+        fullname.to_string()
+    }
+}
+
+impl fmt::Display for Fullname {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}_{}", self.kind, self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{join, Fullname, Kind};
+    use crate::util::RouxError;
+
+    #[test]
+    fn test_parse() {
+        let fullname: Fullname = "t1_c0hjbop".parse().unwrap();
+        assert_eq!(fullname.kind(), Kind::Comment);
+        assert_eq!(fullname.id(), "c0hjbop");
+        assert_eq!(fullname.to_string(), "t1_c0hjbop");
+
+        for invalid in [
+            "abc123",
+            "t7_abc",
+            "t3_",
+            "t3_ABC",
+            "t3_a-b",
+            "t3_zzzzzzzzzzzzzz",
+        ] {
+            assert!(matches!(
+                invalid.parse::<Fullname>(),
+                Err(RouxError::InvalidFullname(ref s)) if s == invalid
+            ));
+        }
+    }
+
+    #[test]
+    fn test_id_number() {
+        let fullname = Fullname::new(Kind::Link, "15bfi0").unwrap();
+        assert_eq!(fullname.id_number(), 69_397_560);
+        assert_eq!(Fullname::from_id_number(Kind::Link, 69_397_560), fullname);
+        assert_eq!(Fullname::from_id_number(Kind::Link, 0).id(), "0");
+    }
+
+    #[test]
+    fn test_serde() {
+        let fullnames: Vec<Fullname> = serde_json::from_str(r#"["t3_a", "t4_b"]"#).unwrap();
+        assert_eq!(join(&fullnames), "t3_a,t4_b");
+        assert_eq!(serde_json::to_string(&fullnames[0]).unwrap(), r#""t3_a""#);
+        assert!(serde_json::from_str::<Fullname>(r#""a""#).is_err());
+    }
+}
@@ -4,8 +4,30 @@ pub mod defaults;
 pub mod error;
 /// Url building.
 pub mod url;
-pub use error::RouxError;
+pub use error::{RedditApiError, RouxError};
+/// Fullnames
+pub mod fullname;
+pub use fullname::{Fullname, Kind};
 /// Options
 pub mod option;
-pub use option::FeedOption;
+pub use option::SortType;
 pub use option::TimePeriod;
+pub use option::{ArticleOption, CommentSort, FeedOption};
+/// Rate limits
+pub mod ratelimit;
+pub use ratelimit::{RateLimit, RateLimiter};
+/// Paginator
+pub mod paginator;
+pub use paginator::{Cursor, ListingChild, ListingItem, Paginator};
+/// Retries
+pub mod retry;
+pub use retry::RetryPolicy;
+/// Live streams
+pub mod stream;
+pub use stream::{InboxOptions, LiveStream, StreamOptions};
+/// Token stores
+pub mod store;
+pub use store::{FileTokenStore, MemoryTokenStore, StoredToken, TokenStore};
+/// TOTP codes
+#[cfg(feature = "totp")]
+pub mod totp;
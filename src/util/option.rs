@@ -5,6 +5,14 @@
 //! "next" and "prev" buttons on the site and in combination with count can be used to page
 //! through the listing.
 
+use crate::util::RouxError;
+
+/// The most items Reddit returns in one page.
+const MAX_LIMIT: u32 = 100;
+
+/// The most parents Reddit shows above a focused comment.
+const MAX_CONTEXT: u32 = 8;
+
 /// Basic feed options
 #[derive(Clone, Debug)]
 pub struct FeedOption {
@@ -12,12 +20,25 @@ pub struct FeedOption {
     pub after: Option<String>,
     /// Only one should be specified.
     pub before: Option<String>,
-    /// The number of items that can be in this listing.
+    /// The number of items that can be in this listing, from 1 to 100.
     pub limit: Option<u32>,
     /// The number of items already seen in this listing.
     pub count: Option<u32>,
     /// What time period to request (only works on some requests, like top)
     pub period: Option<TimePeriod>,
+    /// How to sort the listing (only works on some requests, like search and user listings)
+    pub sort: Option<SortType>,
+    /// Whether to include items the user has hidden or otherwise filtered out.
+    pub show_all: bool,
+    /// Whether to expand the subreddit of each item into an `sr_detail` object.
+    pub sr_detail: bool,
+    /// Whether to return text unescaped. Reddit escapes `<`, `>` and `&` in JSON bodies
+    /// by default.
+    pub raw_json: bool,
+    /// Whether to include the category of saved items.
+    pub include_categories: bool,
+    /// Any other params, sent as they are.
+    pub params: Vec<(String, String)>,
 }
 
 impl FeedOption {
@@ -29,25 +50,23 @@ impl FeedOption {
             count: None,
             limit: None,
             period: None,
+            sort: None,
+            show_all: false,
+            sr_detail: false,
+            raw_json: false,
+            include_categories: false,
+            params: Vec::new(),
         }
     }
 
     /// Set after param.
     pub fn after(mut self, ty: &str) -> FeedOption {
-        if self.before.is_some() {
-            panic!("Cannot have an after and before param at the same time");
-        }
-
         self.after = Some(ty.to_owned());
         self
     }
 
     /// Set before param.
     pub fn before(mut self, ty: &str) -> FeedOption {
-        if self.after.is_some() {
-            panic!("Cannot have an after and before param at the same time");
-        }
-
         self.before = Some(ty.to_owned());
         self
     }
@@ -70,12 +89,59 @@ impl FeedOption {
         self
     }
 
-    /// Build a url from `FeedOption`
-    pub fn build_url(self, url: &mut String) {
+    /// Set sort param.
+    pub fn sort(mut self, sort: SortType) -> FeedOption {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Set show param to `all`.
+    pub fn show_all(mut self) -> FeedOption {
+        self.show_all = true;
+        self
+    }
+
+    /// Set sr_detail param.
+    pub fn sr_detail(mut self) -> FeedOption {
+        self.sr_detail = true;
+        self
+    }
+
+    /// Set raw_json param.
+    pub fn raw_json(mut self) -> FeedOption {
+        self.raw_json = true;
+        self
+    }
+
+    /// Set include_categories param.
+    pub fn include_categories(mut self) -> FeedOption {
+        self.include_categories = true;
+        self
+    }
+
+    /// Set any other param. It is sent after the typed ones.
+    pub fn param(mut self, key: &str, value: &str) -> FeedOption {
+        self.params.push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Build a url from `FeedOption`. Fails if both `after` and `before` are set, or if
+    /// `limit` is not between 1 and 100.
+    pub fn build_url(self, url: &mut String) -> Result<(), RouxError> {
+        if self.after.is_some() && self.before.is_some() {
+            return Err(RouxError::InvalidOption(
+                "Cannot have an after and before param at the same time".to_owned(),
+            ));
+        }
+
+        if let Some(limit) = self.limit {
+            check_limit(limit)?;
+        }
+
         if let Some(after) = self.after {
-            url.push_str(&format!("&after={}", after));
+            push_param(url, "after", &after);
         } else if let Some(before) = self.before {
-            url.push_str(&format!("&before={}", before));
+            push_param(url, "before", &before);
         }
 
         if let Some(count) = self.count {
@@ -90,12 +156,60 @@ impl FeedOption {
             url.push_str(&format!("&t={}", period.get_string_for_period()));
         }
 
+        if let Some(sort) = self.sort {
+            url.push_str(&format!("&sort={}", sort.get_string_for_sort()));
+        }
+
+        if self.show_all {
+            url.push_str("&show=all");
+        }
+
+        if self.sr_detail {
+            url.push_str("&sr_detail=true");
+        }
+
+        if self.raw_json {
+            url.push_str("&raw_json=1");
+        }
+
+        if self.include_categories {
+            url.push_str("&include_categories=true");
+        }
+
+        for (key, value) in &self.params {
+            push_param(url, key, value);
+        }
+
         // HACK : the previous option won't work if a '&' isn't appended for some reason
         // Eg. &after={} won't return correct page
         // Eg. &after={}&limit={} returns correct page but won't return correct limit
         // I have no idea why.
         url.push_str(&String::from("&"));
+
+        Ok(())
+    }
+}
+
+/// Fails if `limit` is not between 1 and 100.
+pub(crate) fn check_limit(limit: u32) -> Result<(), RouxError> {
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(RouxError::InvalidOption(format!(
+            "limit must be between 1 and {}, got {}",
+            MAX_LIMIT, limit
+        )));
     }
+
+    Ok(())
+}
+
+/// `value` encoded for a query string.
+pub(crate) fn encode(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+/// Appends `&key=value` to `url`, encoding both.
+fn push_param(url: &mut String, key: &str, value: &str) {
+    url.push_str(&format!("&{}={}", encode(key), encode(value)));
 }
 
 impl Default for FeedOption {
@@ -135,9 +249,164 @@ impl TimePeriod {
     }
 }
 
+/// Options for getting a post with its comments.
+#[derive(Clone, Debug, Default)]
+pub struct ArticleOption {
+    /// How to sort the comments.
+    pub sort: Option<CommentSort>,
+    /// The number of parents to show above `comment`, from 0 to 8.
+    pub context: Option<u32>,
+    /// The id of a comment to show the thread of, instead of the whole post.
+    pub comment: Option<String>,
+    /// How deep replies go at most.
+    pub depth: Option<u32>,
+    /// The number of comments at most.
+    pub limit: Option<u32>,
+}
+
+impl ArticleOption {
+    /// Create a new `ArticleOption` instance.
+    pub fn new() -> ArticleOption {
+        ArticleOption::default()
+    }
+
+    /// Set sort param.
+    pub fn sort(mut self, sort: CommentSort) -> ArticleOption {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Set context param.
+    pub fn context(mut self, context: u32) -> ArticleOption {
+        self.context = Some(context);
+        self
+    }
+
+    /// Set comment param, the id of the comment without `t1_`.
+    pub fn comment(mut self, comment: &str) -> ArticleOption {
+        self.comment = Some(comment.to_owned());
+        self
+    }
+
+    /// Set depth param.
+    pub fn depth(mut self, depth: u32) -> ArticleOption {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Set limit param.
+    pub fn limit(mut self, limit: u32) -> ArticleOption {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Build a url from `ArticleOption`. Fails if `context` is over 8.
+    pub fn build_url(self, url: &mut String) -> Result<(), RouxError> {
+        if let Some(context) = self.context {
+            if context > MAX_CONTEXT {
+                return Err(RouxError::InvalidOption(format!(
+                    "context must be at most {}, got {}",
+                    MAX_CONTEXT, context
+                )));
+            }
+        }
+
+        if let Some(sort) = self.sort {
+            url.push_str(&format!("&sort={}", sort.get_string_for_sort()));
+        }
+
+        if let Some(context) = self.context {
+            url.push_str(&format!("&context={}", context));
+        }
+
+        if let Some(comment) = self.comment {
+            push_param(url, "comment", &comment);
+        }
+
+        if let Some(depth) = self.depth {
+            url.push_str(&format!("&depth={}", depth));
+        }
+
+        if let Some(limit) = self.limit {
+            url.push_str(&format!("&limit={}", limit));
+        }
+
+        Ok(())
+    }
+}
+
+/// How to sort the comments of a post.
+#[derive(Copy, Clone, Debug)]
+pub enum CommentSort {
+    /// Best first
+    Confidence,
+    /// Highest score first
+    Top,
+    /// Newest first
+    New,
+    /// Most disputed first
+    Controversial,
+    /// Oldest first
+    Old,
+    /// Random order
+    Random,
+    /// Answers by the author first
+    Qa,
+    /// Newest first, updated live
+    Live,
+}
+
+impl CommentSort {
+    /// Gets the request string for the sort
+    pub fn get_string_for_sort(&self) -> &str {
+        match self {
+            CommentSort::Confidence => "confidence",
+            CommentSort::Top => "top",
+            CommentSort::New => "new",
+            CommentSort::Controversial => "controversial",
+            CommentSort::Old => "old",
+            CommentSort::Random => "random",
+            CommentSort::Qa => "qa",
+            CommentSort::Live => "live",
+        }
+    }
+}
+
+/// How to sort a listing. Which orders are allowed depends on the request.
+#[derive(Copy, Clone, Debug)]
+pub enum SortType {
+    /// Most popular right now
+    Hot,
+    /// Newest first
+    New,
+    /// Highest score first
+    Top,
+    /// Most disputed first
+    Controversial,
+    /// Best match first (search only)
+    Relevance,
+    /// Most comments first (search only)
+    Comments,
+}
+
+impl SortType {
+    /// Gets the request string for the sort
+    pub fn get_string_for_sort(&self) -> &str {
+        match self {
+            SortType::Hot => "hot",
+            SortType::New => "new",
+            SortType::Top => "top",
+            SortType::Controversial => "controversial",
+            SortType::Relevance => "relevance",
+            SortType::Comments => "comments",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::FeedOption;
+    use super::{ArticleOption, CommentSort, FeedOption, SortType, TimePeriod};
+    use crate::util::RouxError;
 
     #[test]
     fn test_build_url_after() {
@@ -145,7 +414,7 @@ mod tests {
         let options = FeedOption::new().after(after);
 
         let url = &mut String::from("");
-        options.build_url(url);
+        options.build_url(url).unwrap();
 
         assert!(*url == format!("&after={}&", after))
     }
@@ -156,7 +425,7 @@ mod tests {
         let options = FeedOption::new().before(before);
 
         let url = &mut String::from("");
-        options.build_url(url);
+        options.build_url(url).unwrap();
 
         assert!(*url == format!("&before={}&", before))
     }
@@ -167,8 +436,83 @@ mod tests {
         let options = FeedOption::new().count(count);
 
         let url = &mut String::from("");
-        options.build_url(url);
+        options.build_url(url).unwrap();
 
         assert!(*url == format!("&count={}&", count))
     }
+
+    #[test]
+    fn test_build_url_params() {
+        let options = FeedOption::new()
+            .period(TimePeriod::ThisWeek)
+            .sort(SortType::Top)
+            .show_all()
+            .sr_detail()
+            .raw_json()
+            .include_categories()
+            .param("q", "rust & go");
+
+        let url = &mut String::from("");
+        options.build_url(url).unwrap();
+
+        assert_eq!(
+            *url,
+            "&t=week&sort=top&show=all&sr_detail=true&raw_json=1&include_categories=true&q=rust+%26+go&"
+        );
+    }
+
+    #[test]
+    fn test_build_url_encodes() {
+        let options = FeedOption::new().after("t3_a&limit=5");
+
+        let url = &mut String::from("");
+        options.build_url(url).unwrap();
+
+        assert_eq!(*url, "&after=t3_a%26limit%3D5&");
+    }
+
+    #[test]
+    fn test_build_url_invalid() {
+        let url = &mut String::from("");
+
+        let options = FeedOption::new().after("t3_a").before("t3_b");
+        assert!(matches!(
+            options.build_url(url),
+            Err(RouxError::InvalidOption(_))
+        ));
+
+        let options = FeedOption::new().limit(0);
+        assert!(matches!(
+            options.build_url(url),
+            Err(RouxError::InvalidOption(_))
+        ));
+
+        let options = FeedOption::new().limit(101);
+        assert!(matches!(
+            options.build_url(url),
+            Err(RouxError::InvalidOption(_))
+        ));
+
+        assert!(url.is_empty());
+    }
+
+    #[test]
+    fn test_article_build_url() {
+        let options = ArticleOption::new()
+            .sort(CommentSort::Top)
+            .context(3)
+            .comment("c0hjbop")
+            .depth(2)
+            .limit(50);
+
+        let url = &mut String::from("");
+        options.build_url(url).unwrap();
+        assert_eq!(*url, "&sort=top&context=3&comment=c0hjbop&depth=2&limit=50");
+
+        let options = ArticleOption::new().context(9);
+        assert!(matches!(
+            options.build_url(url),
+            Err(RouxError::InvalidOption(_))
+        ));
+    }
 }
@@ -0,0 +1,574 @@
+//! # Paginator
+//! Walks a listing page by page, copying each page's `after` into the [`FeedOption`] for the
+//! next one, and yields the items one at a time. It stops at the end of the listing, at an
+//! empty page, or once [`Paginator::max_items`] items have been yielded.
+//!
+//! A paginator wraps any method that takes a `FeedOption` and returns a listing. It is a
+//! [`Stream`](futures_core::Stream) in async builds and an [`Iterator`] with the `blocking`
+//! feature.
+//!
+//! ```no_run
+//! use roux::Subreddit;
+//! use roux::util::Paginator;
+//! # #[cfg(not(feature = "blocking"))]
+//! # use futures::StreamExt;
+//! # #[cfg(not(feature = "blocking"))]
+//! # use tokio;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let subreddit = Subreddit::new("rust");
+//! let mut posts = Paginator::new(|options| subreddit.top(100, Some(options))).max_items(250);
+//!
+//! # #[cfg(not(feature = "blocking"))]
+//! while let Some(post) = posts.next().await {
+//!     println!("{}", post.unwrap().title);
+//! }
+//! # }
+//! ```
+//!
+//! A long crawl can be stopped and picked up later: save [`Paginator::cursor`], e.g. as JSON,
+//! and hand it to [`Paginator::resume`] on the next run. If Reddit no longer lists the item
+//! the crawl stopped at, the paginator yields [`RouxError::StaleCursor`] instead of silently
+//! starting over.
+
+use std::collections::VecDeque;
+
+#[cfg(not(feature = "blocking"))]
+use std::future::Future;
+#[cfg(not(feature = "blocking"))]
+use std::pin::Pin;
+#[cfg(not(feature = "blocking"))]
+use std::task::{Context, Poll};
+
+#[cfg(not(feature = "blocking"))]
+use futures_core::Stream;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::comment::CommentData;
+use crate::models::inbox::InboxData;
+use crate::models::response::{BasicThing, Listing};
+use crate::models::submission::SubmissionData;
+use crate::models::subreddit::response::SubredditData;
+use crate::models::thing::Thing;
+use crate::util::{FeedOption, RouxError};
+
+/// How many fullnames a [`Cursor`] keeps to skip items that were already yielded.
+const SEEN_LEN: usize = 100;
+
+/// An item of a listing, which can be used as the anchor of a page.
+pub trait ListingItem {
+    /// The item's fullname, e.g. `t3_abc123`.
+    fn fullname(&self) -> Option<String>;
+}
+
+/// An entry of a listing page, which holds an item. Most listings wrap their items in a
+/// [`BasicThing`], mixed ones hold [`Thing`]s.
+pub trait ListingChild {
+    /// The item.
+    type Item: ListingItem;
+
+    /// Unwraps the item.
+    fn into_item(self) -> Self::Item;
+}
+
+/// Where a crawl stopped, to resume it with [`Paginator::resume`]. It can be serialized and
+/// saved between runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// The fullname of the last item yielded.
+    pub after: Option<String>,
+    /// The number of items yielded before and including `after`.
+    pub count: u32,
+    /// The fullnames of the last items yielded, so they are skipped if the listing shifts.
+    pub seen: Vec<String>,
+    /// Whether the end of the listing was reached.
+    pub exhausted: bool,
+}
+
+/// The paging state shared by the async and blocking paginators.
+struct Pages<T> {
+    options: FeedOption,
+    after: Option<String>,
+    count: u32,
+    buffer: VecDeque<T>,
+    max_items: Option<usize>,
+    yielded: usize,
+    done: bool,
+    cursor: Cursor,
+    resumed: bool,
+}
+
+impl<T: ListingItem> Pages<T> {
+    fn new() -> Pages<T> {
+        Pages {
+            options: FeedOption::new(),
+            after: None,
+            count: 0,
+            buffer: VecDeque::new(),
+            max_items: None,
+            yielded: 0,
+            done: false,
+            cursor: Cursor::default(),
+            resumed: false,
+        }
+    }
+
+    /// Continue from `cursor` instead of the start of the listing.
+    fn resume(&mut self, cursor: Cursor) {
+        self.after = cursor.after.to_owned();
+        self.count = cursor.count;
+        self.resumed = cursor.after.is_some();
+        self.cursor = cursor;
+    }
+
+    /// The options for the next page.
+    fn next_options(&self) -> FeedOption {
+        let mut options = self.options.to_owned();
+
+        if let Some(after) = &self.after {
+            options.after = Some(after.to_owned());
+            options.before = None;
+            options.count = Some(self.count);
+        }
+
+        options
+    }
+
+    /// Queue the items of a page and remember where the next one starts.
+    fn push_page<C>(&mut self, listing: BasicThing<Listing<C>>) -> Option<RouxError>
+    where
+        C: ListingChild<Item = T>,
+    {
+        let listing = listing.data;
+        let resumed = std::mem::take(&mut self.resumed);
+
+        if listing.children.is_empty() || listing.after.is_none() {
+            self.done = true;
+        }
+
+        // Reddit answers with an empty page when the anchor was deleted or has fallen out of
+        // the listing, so a crawl that had not reached the end cannot carry on from it.
+        if resumed && listing.children.is_empty() && !self.cursor.exhausted {
+            return Some(RouxError::StaleCursor(
+                self.after.to_owned().unwrap_or_default(),
+            ));
+        }
+
+        self.count += listing.children.len() as u32;
+        self.after = listing.after;
+
+        let seen = &self.cursor.seen;
+        self.buffer.extend(
+            listing
+                .children
+                .into_iter()
+                .map(ListingChild::into_item)
+                .filter(|item| item.fullname().is_none_or(|name| !seen.contains(&name))),
+        );
+
+        None
+    }
+
+    fn capped(&self) -> bool {
+        matches!(self.max_items, Some(max_items) if self.yielded >= max_items)
+    }
+
+    /// The next queued item, unless the cap has been reached.
+    fn pop(&mut self) -> Option<T> {
+        if self.capped() {
+            return None;
+        }
+
+        let item = self.buffer.pop_front()?;
+        self.yielded += 1;
+        self.cursor.count += 1;
+
+        if let Some(name) = item.fullname() {
+            if self.cursor.seen.len() >= SEEN_LEN {
+                self.cursor.seen.remove(0);
+            }
+            self.cursor.seen.push(name.to_owned());
+            self.cursor.after = Some(name);
+        }
+
+        Some(item)
+    }
+
+    /// Whether there is nothing left to yield or fetch.
+    fn finished(&self) -> bool {
+        self.capped() || (self.done && self.buffer.is_empty())
+    }
+
+    /// Where the crawl is now.
+    fn cursor(&self) -> Cursor {
+        Cursor {
+            exhausted: self.done && self.buffer.is_empty(),
+            ..self.cursor.to_owned()
+        }
+    }
+}
+
+impl ListingItem for SubmissionData {
+    fn fullname(&self) -> Option<String> {
+        Some(self.name.to_owned())
+    }
+}
+
+impl ListingItem for CommentData {
+    fn fullname(&self) -> Option<String> {
+        self.name.to_owned()
+    }
+}
+
+impl ListingItem for InboxData {
+    fn fullname(&self) -> Option<String> {
+        Some(self.name.to_owned())
+    }
+}
+
+impl ListingItem for SubredditData {
+    fn fullname(&self) -> Option<String> {
+        self.name.to_owned()
+    }
+}
+
+impl ListingItem for Thing {
+    fn fullname(&self) -> Option<String> {
+        match self {
+            Thing::Comment(comment) => comment.fullname(),
+            Thing::Account(account) => account.id.as_ref().map(|id| format!("t2_{}", id)),
+            Thing::Submission(submission) => submission.fullname(),
+            Thing::Message(message) => message.fullname(),
+            Thing::Subreddit(subreddit) => subreddit.fullname(),
+            // Not an item of the listing, so never an anchor.
+            Thing::More(_) => None,
+        }
+    }
+}
+
+impl<T: ListingItem> ListingChild for BasicThing<T> {
+    type Item = T;
+
+    fn into_item(self) -> T {
+        self.data
+    }
+}
+
+impl ListingChild for Thing {
+    type Item = Thing;
+
+    fn into_item(self) -> Thing {
+        self
+    }
+}
+
+/// Yields the items of a listing across pages. See the [module docs](self).
+#[cfg(not(feature = "blocking"))]
+pub struct Paginator<T, F, Fut> {
+    pages: Pages<T>,
+    fetch: F,
+    pending: Option<Pin<Box<Fut>>>,
+}
+
+/// Yields the items of a listing across pages. See the [module docs](self).
+#[cfg(feature = "blocking")]
+pub struct Paginator<T, F> {
+    pages: Pages<T>,
+    fetch: F,
+}
+
+#[cfg(not(feature = "blocking"))]
+impl<T, C, F, Fut> Paginator<T, F, Fut>
+where
+    T: ListingItem,
+    C: ListingChild<Item = T>,
+    F: FnMut(FeedOption) -> Fut,
+    Fut: Future<Output = Result<BasicThing<Listing<C>>, RouxError>>,
+{
+    /// Create a `Paginator` that gets each page by calling `fetch`.
+    pub fn new(fetch: F) -> Paginator<T, F, Fut> {
+        Paginator {
+            pages: Pages::new(),
+            fetch,
+            pending: None,
+        }
+    }
+
+    /// Sets the options every page is requested with, e.g. the page size or time period.
+    /// `after`, `before` and `count` are overwritten from the second page on.
+    pub fn options(mut self, options: FeedOption) -> Paginator<T, F, Fut> {
+        self.pages.options = options;
+        self
+    }
+
+    /// Sets how many items to yield at most, across all pages.
+    pub fn max_items(mut self, max_items: usize) -> Paginator<T, F, Fut> {
+        self.pages.max_items = Some(max_items);
+        self
+    }
+
+    /// Continue a crawl from where `cursor` stopped. If Reddit no longer honours its anchor,
+    /// the first item yielded is [`RouxError::StaleCursor`].
+    pub fn resume(mut self, cursor: Cursor) -> Paginator<T, F, Fut> {
+        self.pages.resume(cursor);
+        self
+    }
+
+    /// Where the crawl is now, to save and [`resume`](Paginator::resume) later.
+    pub fn cursor(&self) -> Cursor {
+        self.pages.cursor()
+    }
+}
+
+#[cfg(not(feature = "blocking"))]
+impl<T, C, F, Fut> Stream for Paginator<T, F, Fut>
+where
+    T: ListingItem + Unpin,
+    C: ListingChild<Item = T>,
+    F: FnMut(FeedOption) -> Fut + Unpin,
+    Fut: Future<Output = Result<BasicThing<Listing<C>>, RouxError>>,
+{
+    type Item = Result<T, RouxError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Some(item) = this.pages.pop() {
+                return Poll::Ready(Some(Ok(item)));
+            }
+
+            if this.pages.finished() {
+                return Poll::Ready(None);
+            }
+
+            let pending = match &mut this.pending {
+                Some(pending) => pending,
+                None => this
+                    .pending
+                    .insert(Box::pin((this.fetch)(this.pages.next_options()))),
+            };
+
+            let page = match pending.as_mut().poll(cx) {
+                Poll::Ready(page) => page,
+                Poll::Pending => return Poll::Pending,
+            };
+            this.pending = None;
+
+            match page {
+                Ok(listing) => {
+                    if let Some(e) = this.pages.push_page(listing) {
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+                Err(e) => {
+                    this.pages.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T, C, F> Paginator<T, F>
+where
+    T: ListingItem,
+    C: ListingChild<Item = T>,
+    F: FnMut(FeedOption) -> Result<BasicThing<Listing<C>>, RouxError>,
+{
+    /// Create a `Paginator` that gets each page by calling `fetch`.
+    pub fn new(fetch: F) -> Paginator<T, F> {
+        Paginator {
+            pages: Pages::new(),
+            fetch,
+        }
+    }
+
+    /// Sets the options every page is requested with, e.g. the page size or time period.
+    /// `after`, `before` and `count` are overwritten from the second page on.
+    pub fn options(mut self, options: FeedOption) -> Paginator<T, F> {
+        self.pages.options = options;
+        self
+    }
+
+    /// Sets how many items to yield at most, across all pages.
+    pub fn max_items(mut self, max_items: usize) -> Paginator<T, F> {
+        self.pages.max_items = Some(max_items);
+        self
+    }
+
+    /// Continue a crawl from where `cursor` stopped. If Reddit no longer honours its anchor,
+    /// the first item yielded is [`RouxError::StaleCursor`].
+    pub fn resume(mut self, cursor: Cursor) -> Paginator<T, F> {
+        self.pages.resume(cursor);
+        self
+    }
+
+    /// Where the crawl is now, to save and [`resume`](Paginator::resume) later.
+    pub fn cursor(&self) -> Cursor {
+        self.pages.cursor()
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T, C, F> Iterator for Paginator<T, F>
+where
+    T: ListingItem,
+    C: ListingChild<Item = T>,
+    F: FnMut(FeedOption) -> Result<BasicThing<Listing<C>>, RouxError>,
+{
+    type Item = Result<T, RouxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pages.pop() {
+                return Some(Ok(item));
+            }
+
+            if self.pages.finished() {
+                return None;
+            }
+
+            match (self.fetch)(self.pages.next_options()) {
+                Ok(listing) => {
+                    if let Some(e) = self.pages.push_page(listing) {
+                        return Some(Err(e));
+                    }
+                }
+                Err(e) => {
+                    self.pages.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cursor, ListingItem, Pages};
+    use crate::models::response::BasicListing;
+    use crate::util::RouxError;
+
+    impl ListingItem for String {
+        fn fullname(&self) -> Option<String> {
+            Some(format!("t3_{}", self))
+        }
+    }
+
+    fn page(ids: &[&str], after: Option<&str>) -> BasicListing<String> {
+        serde_json::from_value(serde_json::json!({
+            "kind": "Listing",
+            "data": {
+                "after": after,
+                "before": null,
+                "children": ids
+                    .iter()
+                    .map(|id| serde_json::json!({"kind": "t3", "data": id}))
+                    .collect::<Vec<_>>(),
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pages() {
+        let mut pages = Pages::new();
+        assert!(pages.next_options().after.is_none());
+
+        assert!(pages.push_page(page(&["a", "b"], Some("t3_b"))).is_none());
+        assert_eq!(pages.pop().as_deref(), Some("a"));
+        assert_eq!(pages.pop().as_deref(), Some("b"));
+        assert_eq!(pages.pop(), None);
+        assert!(!pages.finished());
+
+        let options = pages.next_options();
+        assert_eq!(options.after.as_deref(), Some("t3_b"));
+        assert_eq!(options.count, Some(2));
+
+        pages.push_page(page(&["c"], None));
+        assert_eq!(pages.pop().as_deref(), Some("c"));
+        assert!(pages.finished());
+    }
+
+    #[test]
+    fn test_empty_page() {
+        let mut pages: Pages<String> = Pages::new();
+        pages.push_page(page(&[], Some("t3_b")));
+        assert!(pages.finished());
+    }
+
+    #[test]
+    fn test_max_items() {
+        let mut pages = Pages::new();
+        pages.max_items = Some(1);
+        pages.push_page(page(&["a", "b"], Some("t3_b")));
+
+        assert_eq!(pages.pop().as_deref(), Some("a"));
+        assert_eq!(pages.pop(), None);
+        assert!(pages.finished());
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut pages = Pages::new();
+        pages.push_page(page(&["a", "b", "c"], Some("t3_c")));
+        assert_eq!(pages.pop().as_deref(), Some("a"));
+        assert_eq!(pages.pop().as_deref(), Some("b"));
+
+        // The cursor points at the last item yielded, not the end of the page.
+        let cursor = pages.cursor();
+        assert_eq!(cursor.after.as_deref(), Some("t3_b"));
+        assert_eq!(cursor.count, 2);
+        assert_eq!(cursor.seen, ["t3_a", "t3_b"]);
+        assert!(!cursor.exhausted);
+
+        let json = serde_json::to_string(&cursor).unwrap();
+        let cursor: Cursor = serde_json::from_str(&json).unwrap();
+
+        let mut pages = Pages::new();
+        pages.resume(cursor);
+        let options = pages.next_options();
+        assert_eq!(options.after.as_deref(), Some("t3_b"));
+        assert_eq!(options.count, Some(2));
+
+        // Items that were already yielded are skipped if the listing shifted.
+        assert!(pages.push_page(page(&["b", "c"], None)).is_none());
+        assert_eq!(pages.pop().as_deref(), Some("c"));
+        assert_eq!(pages.pop(), None);
+
+        let cursor = pages.cursor();
+        assert_eq!(cursor.count, 3);
+        assert!(cursor.exhausted);
+    }
+
+    #[test]
+    fn test_stale_cursor() {
+        let cursor = Cursor {
+            after: Some("t3_gone".to_owned()),
+            count: 10,
+            seen: Vec::new(),
+            exhausted: false,
+        };
+
+        let mut pages: Pages<String> = Pages::new();
+        pages.resume(cursor.to_owned());
+        assert!(matches!(
+            pages.push_page(page(&[], None)),
+            Some(RouxError::StaleCursor(anchor)) if anchor == "t3_gone"
+        ));
+        assert!(pages.finished());
+
+        // An empty page after the end of the listing just means nothing new was added.
+        let mut pages: Pages<String> = Pages::new();
+        pages.resume(Cursor {
+            exhausted: true,
+            ..cursor
+        });
+        assert!(pages.push_page(page(&[], None)).is_none());
+    }
+}
@@ -0,0 +1,154 @@
+//! # Rate limits
+//! Reddit reports how many requests are left in the current window with the
+//! `X-Ratelimit-Remaining`, `X-Ratelimit-Used` and `X-Ratelimit-Reset` headers. Every handle
+//! records them, and once the budget runs out, waits for the window to reset before sending
+//! more requests instead of getting `429 Too Many Requests`.
+//!
+//! Handles created from the same [`Reddit`](crate::Reddit) share one limiter, since Reddit
+//! counts requests per login.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::header::HeaderMap;
+
+/// The request budget Reddit last reported, less the requests sent since.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Requests left in the current window.
+    pub remaining: f64,
+    /// Requests used in the current window.
+    pub used: f64,
+    /// When the current window ends and the budget is reset.
+    pub reset: Instant,
+}
+
+impl RateLimit {
+    /// Time until the current window ends.
+    pub fn reset_in(&self) -> Duration {
+        self.reset.saturating_duration_since(Instant::now())
+    }
+}
+
+/// Tracks the request budget for every handle it is shared with.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    limit: Arc<Mutex<Option<RateLimit>>>,
+}
+
+impl RateLimiter {
+    /// Create a `RateLimiter` that knows nothing about the budget yet.
+    pub fn new() -> RateLimiter {
+        RateLimiter::default()
+    }
+
+    /// The current budget, or `None` if no response has reported one yet or the window it
+    /// was reported for has ended.
+    pub fn current(&self) -> Option<RateLimit> {
+        let limit = *self.limit.lock().unwrap();
+        limit.filter(|limit| limit.reset > Instant::now())
+    }
+
+    /// Record the budget reported by a response's headers.
+    pub(crate) fn update(&self, headers: &HeaderMap) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<f64>().ok())
+        };
+
+        // A reset too far off to represent is ignored rather than trusted.
+        let reset = header("x-ratelimit-reset")
+            .and_then(|reset| Duration::try_from_secs_f64(reset.max(0.0)).ok())
+            .and_then(|reset| Instant::now().checked_add(reset));
+
+        if let (Some(remaining), Some(reset)) = (header("x-ratelimit-remaining"), reset) {
+            *self.limit.lock().unwrap() = Some(RateLimit {
+                remaining,
+                used: header("x-ratelimit-used").unwrap_or_default(),
+                reset,
+            });
+        }
+    }
+
+    /// Take one request from the budget. Returns how long to wait first if there is none left.
+    pub(crate) fn acquire(&self) -> Option<Duration> {
+        let mut limit = self.limit.lock().unwrap();
+        let limit = limit.as_mut()?;
+        let now = Instant::now();
+
+        if limit.reset <= now {
+            // The window has ended, the next response will report the new budget.
+            None
+        } else if limit.remaining >= 1.0 {
+            limit.remaining -= 1.0;
+            limit.used += 1.0;
+            None
+        } else {
+            Some(limit.reset - now)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    use super::RateLimiter;
+
+    fn headers(remaining: &str, reset: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-ratelimit-remaining",
+            HeaderValue::from_str(remaining).unwrap(),
+        );
+        headers.insert("x-ratelimit-used", HeaderValue::from_static("10"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_str(reset).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_acquire() {
+        let limiter = RateLimiter::new();
+        assert!(limiter.current().is_none());
+        assert!(limiter.acquire().is_none());
+
+        limiter.update(&headers("2.0", "60"));
+        assert_eq!(limiter.current().unwrap().remaining, 2.0);
+        assert_eq!(limiter.current().unwrap().used, 10.0);
+
+        assert!(limiter.acquire().is_none());
+        assert!(limiter.acquire().is_none());
+        assert_eq!(limiter.current().unwrap().remaining, 0.0);
+        assert_eq!(limiter.current().unwrap().used, 12.0);
+
+        let wait = limiter.acquire().unwrap();
+        assert!(wait.as_secs() > 50 && wait.as_secs() <= 60);
+    }
+
+    #[test]
+    fn test_window_ended() {
+        let limiter = RateLimiter::new();
+        limiter.update(&headers("0", "0"));
+
+        assert!(limiter.current().is_none());
+        assert!(limiter.acquire().is_none());
+    }
+
+    #[test]
+    fn test_invalid_reset() {
+        let limiter = RateLimiter::new();
+        limiter.update(&headers("2.0", "inf"));
+        limiter.update(&headers("2.0", "1e300"));
+        limiter.update(&headers("2.0", "1e19"));
+        assert!(limiter.current().is_none());
+    }
+
+    #[test]
+    fn test_missing_headers() {
+        let limiter = RateLimiter::new();
+        limiter.update(&HeaderMap::new());
+        assert!(limiter.current().is_none());
+    }
+}
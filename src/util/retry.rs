@@ -0,0 +1,216 @@
+//! # Retries
+//! Reddit regularly answers with `500`, `502`, `503` or `504` under load, and connections
+//! are sometimes reset. A [`RetryPolicy`] sends such requests again after a growing, randomised
+//! delay, or after the delay asked for by a `Retry-After` header.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use roux::Reddit;
+//! use roux::util::RetryPolicy;
+//!
+//! let reddit = Reddit::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET").retry_policy(
+//!     RetryPolicy::new()
+//!         .max_attempts(5)
+//!         .base_delay(Duration::from_millis(250)),
+//! );
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+
+/// When and how often to retry requests that failed for reasons that may go away by
+/// themselves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, starting half a second apart, and only for requests that are safe to
+    /// send twice.
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create the default `RetryPolicy`.
+    pub fn new() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    /// A `RetryPolicy` that sends every request only once.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::default().max_attempts(1)
+    }
+
+    /// Sets how many times a request is sent at most, including the first time.
+    pub fn max_attempts(mut self, max_attempts: u32) -> RetryPolicy {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry. It doubles for every retry after that.
+    pub fn base_delay(mut self, base_delay: Duration) -> RetryPolicy {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the longest delay between two attempts, including delays asked for by
+    /// `Retry-After`.
+    pub fn max_delay(mut self, max_delay: Duration) -> RetryPolicy {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets whether requests that are not safe to send twice, like submitting a post or
+    /// sending a message, are retried too. Off by default, since a request that timed out
+    /// may still have gone through.
+    pub fn retry_non_idempotent(mut self, retry_non_idempotent: bool) -> RetryPolicy {
+        self.retry_non_idempotent = retry_non_idempotent;
+        self
+    }
+
+    /// Whether a request may be sent again after `attempt` attempts.
+    pub(crate) fn allows(&self, attempt: u32, idempotent: bool) -> bool {
+        attempt < self.max_attempts && (idempotent || self.retry_non_idempotent)
+    }
+
+    /// How long to wait before the retry after `attempt` attempts. Uses the response's
+    /// `Retry-After` header if it has one, otherwise exponential backoff with jitter.
+    pub(crate) fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        if let Some(retry_after) = headers.and_then(retry_after) {
+            return retry_after.min(self.max_delay);
+        }
+
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+
+        // Wait between half and all of the backoff, so clients that failed together do not
+        // all retry together.
+        backoff / 2 + backoff.mul_f64(jitter() / 2.0)
+    }
+}
+
+/// Whether a response with `status` is worth retrying.
+pub(crate) fn is_transient(status: u16) -> bool {
+    matches!(status, 429 | 500 | 502 | 503 | 504)
+}
+
+/// The delay asked for by a `Retry-After` header in seconds. Reddit does not send the http
+/// date form.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+}
+
+/// The time until the rate limit window resets, from the `X-Ratelimit-Reset` header.
+pub(crate) fn ratelimit_reset(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get("x-ratelimit-reset")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+}
+
+/// A random number in `[0, 1)`, good enough for spreading out retries.
+fn jitter() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+    use super::{is_transient, retry_after, RetryPolicy};
+
+    #[test]
+    fn test_allows() {
+        let policy = RetryPolicy::new();
+        assert!(policy.allows(1, true));
+        assert!(policy.allows(2, true));
+        assert!(!policy.allows(3, true));
+        assert!(!policy.allows(1, false));
+
+        let policy = policy.retry_non_idempotent(true);
+        assert!(policy.allows(1, false));
+
+        assert!(!RetryPolicy::none().allows(1, true));
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::new()
+            .base_delay(Duration::from_secs(1))
+            .max_delay(Duration::from_secs(5));
+
+        for _ in 0..20 {
+            let first = policy.delay(1, None);
+            assert!(first >= Duration::from_millis(500) && first <= Duration::from_secs(1));
+
+            let second = policy.delay(2, None);
+            assert!(second >= Duration::from_secs(1) && second <= Duration::from_secs(2));
+
+            let capped = policy.delay(10, None);
+            assert!(capped >= Duration::from_millis(2500) && capped <= Duration::from_secs(5));
+        }
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let policy = RetryPolicy::new().max_delay(Duration::from_secs(10));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3"));
+        assert_eq!(policy.delay(1, Some(&headers)), Duration::from_secs(3));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(policy.delay(1, Some(&headers)), Duration::from_secs(10));
+
+        for invalid in ["-1", "inf", "1e300"] {
+            headers.insert(RETRY_AFTER, HeaderValue::from_static(invalid));
+            assert!(retry_after(&headers).is_none());
+        }
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(503));
+        assert!(is_transient(429));
+        assert!(!is_transient(404));
+        assert!(!is_transient(200));
+    }
+}
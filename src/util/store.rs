@@ -0,0 +1,250 @@
+//! # Token stores
+//! A [`TokenStore`] lets a login outlive the process that made it. When one is given to
+//! [`Reddit::token_store`](crate::Reddit::token_store), `Reddit` restores a still valid access
+//! token (or a refresh token) from it instead of asking Reddit for a new one, and saves every
+//! token it gets or refreshes back to it.
+//!
+//! ```no_run
+//! use roux::Reddit;
+//! use roux::util::FileTokenStore;
+//! # #[cfg(not(feature = "blocking"))]
+//! # use tokio;
+//!
+//! # #[cfg_attr(not(feature = "blocking"), tokio::main)]
+//! # #[maybe_async::maybe_async]
+//! # async fn main() {
+//! let me = Reddit::new("USER_AGENT", "CLIENT_ID", "CLIENT_SECRET")
+//!     .username("USERNAME")
+//!     .password("PASSWORD")
+//!     .token_store(FileTokenStore::new("tokens.json"))
+//!     .login()
+//!     .await;
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// Tells apart the temporary files of saves in the same process.
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A token as saved in a [`TokenStore`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredToken {
+    /// The access token.
+    pub access_token: String,
+    /// The refresh token, if the login has one.
+    pub refresh_token: Option<String>,
+    /// The scopes granted to the token.
+    pub scopes: Vec<String>,
+    /// When the access token expires, in seconds since the unix epoch, if known.
+    pub expires_at: Option<u64>,
+}
+
+impl StoredToken {
+    /// Seconds until the access token expires, or `None` if the expiry is not known.
+    /// Expired tokens have `Some(0)`.
+    pub fn expires_in(&self) -> Option<u64> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        self.expires_at
+            .map(|expires_at| expires_at.saturating_sub(now))
+    }
+}
+
+/// Somewhere to keep tokens between runs. Tokens are keyed by client id and the kind of
+/// login: the username for user logins, the device id or `app` for app-only logins. One store
+/// can hold tokens for many accounts.
+pub trait TokenStore: fmt::Debug + Send + Sync {
+    /// Load the token saved under `key`, if there is one.
+    fn load(&self, key: &str) -> io::Result<Option<StoredToken>>;
+    /// Save `token` under `key`, replacing any token already there.
+    fn save(&self, key: &str, token: &StoredToken) -> io::Result<()>;
+}
+
+/// Keeps tokens in memory, so they are shared by every login in the process but lost
+/// when it exits.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: Mutex<HashMap<String, StoredToken>>,
+}
+
+impl MemoryTokenStore {
+    /// Create an empty `MemoryTokenStore`.
+    pub fn new() -> MemoryTokenStore {
+        MemoryTokenStore::default()
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn load(&self, key: &str) -> io::Result<Option<StoredToken>> {
+        Ok(self.tokens.lock().unwrap().get(key).cloned())
+    }
+
+    fn save(&self, key: &str, token: &StoredToken) -> io::Result<()> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(key.to_owned(), token.to_owned());
+        Ok(())
+    }
+}
+
+/// Keeps tokens in a JSON file, as an object of keys to tokens. The file is created on the
+/// first save, readable only by its owner on Unix.
+#[derive(Debug)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileTokenStore {
+    /// Create a `FileTokenStore` that reads and writes `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> FileTokenStore {
+        FileTokenStore {
+            path: path.as_ref().to_owned(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> io::Result<HashMap<String, StoredToken>> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// A temporary file next to the store, unique to this process and save.
+    fn tmp_path(&self) -> PathBuf {
+        let mut name = self
+            .path
+            .file_name()
+            .map(OsString::from)
+            .unwrap_or_default();
+        name.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        self.path.with_file_name(name)
+    }
+}
+
+/// Create `path` and write `bytes` to it. The tokens in it are secrets, so on Unix only the
+/// owner may read it.
+fn write_new(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self, key: &str) -> io::Result<Option<StoredToken>> {
+        let _lock = self.lock.lock().unwrap();
+        Ok(self.read()?.remove(key))
+    }
+
+    fn save(&self, key: &str, token: &StoredToken) -> io::Result<()> {
+        let _lock = self.lock.lock().unwrap();
+
+        let mut tokens = self.read()?;
+        tokens.insert(key.to_owned(), token.to_owned());
+
+        // Write to a temporary file and rename it over the old one, so a crash mid-write
+        // does not lose every token in the file. Each save has its own temporary file, as
+        // several processes can share the store.
+        let tmp = self.tmp_path();
+        let result = write_new(&tmp, &serde_json::to_vec_pretty(&tokens)?)
+            .and_then(|_| fs::rename(&tmp, &self.path));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::{FileTokenStore, MemoryTokenStore, StoredToken, TokenStore};
+
+    fn token(access_token: &str) -> StoredToken {
+        StoredToken {
+            access_token: access_token.to_owned(),
+            refresh_token: Some("refresh".to_owned()),
+            scopes: vec!["identity".to_owned()],
+            expires_at: Some(0),
+        }
+    }
+
+    #[test]
+    fn test_memory_store() {
+        let store = MemoryTokenStore::new();
+        assert_eq!(store.load("key").unwrap(), None);
+
+        store.save("key", &token("access")).unwrap();
+        assert_eq!(store.load("key").unwrap(), Some(token("access")));
+        assert_eq!(store.load("other").unwrap(), None);
+    }
+
+    #[test]
+    fn test_file_store() {
+        let path = env::temp_dir().join(format!("roux-tokens-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let store = FileTokenStore::new(&path);
+        assert_eq!(store.load("key").unwrap(), None);
+
+        store.save("key", &token("access")).unwrap();
+        store.save("other", &token("other")).unwrap();
+
+        let store = FileTokenStore::new(&path);
+        assert_eq!(store.load("key").unwrap(), Some(token("access")));
+        assert_eq!(store.load("other").unwrap(), Some(token("other")));
+        assert_eq!(token("access").expires_in(), Some(0));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // No temporary files are left behind.
+        assert_ne!(store.tmp_path(), store.tmp_path());
+        let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+        let leftovers = fs::read_dir(env::temp_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let file = entry.file_name();
+                let file = file.to_string_lossy();
+                file.starts_with(&name) && file.ends_with(".tmp")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
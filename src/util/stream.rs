@@ -0,0 +1,506 @@
+//! # Live streams
+//! Follow a listing as new items arrive, e.g. with
+//! [`Subreddit::stream_submissions`](crate::Subreddit::stream_submissions). The listing is
+//! polled, items that were already yielded are skipped, and the rest are yielded oldest
+//! first. The wait between polls grows while nothing new arrives and resets once something
+//! does.
+//!
+//! Errors are yielded too, after which the stream backs off and carries on polling, so a
+//! stream only ends when it is dropped.
+//!
+//! A live stream is a [`Stream`](futures_core::Stream) in async builds and an [`Iterator`]
+//! with the `blocking` feature.
+
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+#[cfg(not(feature = "blocking"))]
+use std::future::Future;
+#[cfg(not(feature = "blocking"))]
+use std::pin::Pin;
+#[cfg(not(feature = "blocking"))]
+use std::task::{Context, Poll};
+
+#[cfg(not(feature = "blocking"))]
+use futures_core::Stream;
+
+use crate::client;
+use crate::models::response::{BasicThing, Listing};
+use crate::models::thing::Thing;
+use crate::util::{ListingChild, RouxError};
+
+/// How a live stream polls.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamOptions {
+    skip_existing: bool,
+    min_interval: Duration,
+    max_interval: Duration,
+    seen_capacity: usize,
+}
+
+impl Default for StreamOptions {
+    /// Yield the backlog, poll every 5 to 60 seconds and remember the last 1000 items.
+    fn default() -> StreamOptions {
+        StreamOptions {
+            skip_existing: false,
+            min_interval: Duration::from_secs(5),
+            max_interval: Duration::from_secs(60),
+            seen_capacity: 1000,
+        }
+    }
+}
+
+impl StreamOptions {
+    /// Create the default `StreamOptions`.
+    pub fn new() -> StreamOptions {
+        StreamOptions::default()
+    }
+
+    /// Sets whether the items already in the listing when the stream starts are skipped, so
+    /// only items that arrive afterwards are yielded.
+    pub fn skip_existing(mut self, skip_existing: bool) -> StreamOptions {
+        self.skip_existing = skip_existing;
+        self
+    }
+
+    /// Sets the wait between polls after new items arrived.
+    pub fn min_interval(mut self, min_interval: Duration) -> StreamOptions {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Sets the longest wait between polls. The wait doubles after every poll with nothing
+    /// new, or that failed, up to this.
+    pub fn max_interval(mut self, max_interval: Duration) -> StreamOptions {
+        self.max_interval = max_interval;
+        self
+    }
+
+    /// Sets how many item ids are remembered to skip items that were already yielded. It
+    /// should be well above the number of items a poll returns.
+    pub fn seen_capacity(mut self, seen_capacity: usize) -> StreamOptions {
+        self.seen_capacity = seen_capacity.max(1);
+        self
+    }
+}
+
+/// Which inbox items [`Me::stream_inbox`](crate::Me::stream_inbox) yields, and what it does
+/// with them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InboxOptions {
+    mark_read: bool,
+    types: Vec<String>,
+    was_comment: Option<bool>,
+}
+
+impl InboxOptions {
+    /// Create `InboxOptions` that yield every unread item and leave them unread.
+    pub fn new() -> InboxOptions {
+        InboxOptions::default()
+    }
+
+    /// Sets whether items are marked read as they are yielded, so they are not yielded
+    /// again after a restart. If marking an item fails, the error is yielded in its place
+    /// and the item is yielded again by a later poll.
+    pub fn mark_read(mut self, mark_read: bool) -> InboxOptions {
+        self.mark_read = mark_read;
+        self
+    }
+
+    /// Only yield items of these types, e.g. `username_mention`, `comment_reply`,
+    /// `post_reply`, or `unknown` for private messages.
+    pub fn types(mut self, types: &[&str]) -> InboxOptions {
+        self.types = types.iter().map(|ty| ty.to_string()).collect();
+        self
+    }
+
+    /// Only yield comments (`true`) or only private messages (`false`).
+    pub fn was_comment(mut self, was_comment: bool) -> InboxOptions {
+        self.was_comment = Some(was_comment);
+        self
+    }
+
+    /// Whether these options yield `item`.
+    pub(crate) fn matches(&self, item: &Thing) -> bool {
+        let (ty, item_was_comment) = match item {
+            Thing::Comment(comment) => (comment.r#type.as_deref(), true),
+            Thing::Message(message) => (Some(message.r#type.as_str()), message.was_comment),
+            _ => (None, false),
+        };
+
+        (self.types.is_empty() || ty.is_some_and(|ty| self.types.iter().any(|t| t == ty)))
+            && self
+                .was_comment
+                .is_none_or(|was_comment| was_comment == item_was_comment)
+    }
+
+    /// Whether yielded items are marked read.
+    pub(crate) fn marks_read(&self) -> bool {
+        self.mark_read
+    }
+}
+
+/// The ids of the most recently yielded items, forgetting the oldest once full.
+struct Seen {
+    ids: HashSet<String>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl Seen {
+    fn new(capacity: usize) -> Seen {
+        Seen {
+            ids: HashSet::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Remember `id`. Returns `false` if it was already remembered.
+    fn insert(&mut self, id: String) -> bool {
+        if self.ids.contains(&id) {
+            return false;
+        }
+
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+
+        self.ids.insert(id.to_owned());
+        self.order.push_back(id);
+        true
+    }
+
+    /// Forget `id`, so it is new again.
+    fn remove(&mut self, id: &str) {
+        if self.ids.remove(id) {
+            self.order.retain(|seen| seen != id);
+        }
+    }
+}
+
+/// The polling state behind a live stream.
+pub(crate) struct Poller<T> {
+    options: StreamOptions,
+    id: fn(&T) -> Option<String>,
+    seen: Seen,
+    buffer: VecDeque<T>,
+    interval: Duration,
+    polled: bool,
+    skipped: bool,
+}
+
+impl<T> Poller<T> {
+    /// Create a `Poller` that tells items apart by `id`. Items without an id are always new.
+    pub(crate) fn new(options: StreamOptions, id: fn(&T) -> Option<String>) -> Poller<T> {
+        Poller {
+            options,
+            id,
+            seen: Seen::new(options.seen_capacity),
+            buffer: VecDeque::new(),
+            interval: options.min_interval,
+            polled: false,
+            skipped: !options.skip_existing,
+        }
+    }
+
+    /// The next new item, if any are queued.
+    fn pop(&mut self) -> Option<T> {
+        self.buffer.pop_front()
+    }
+
+    /// How long to wait before the next poll.
+    fn wait(&self) -> Option<Duration> {
+        if self.polled {
+            Some(self.interval)
+        } else {
+            None
+        }
+    }
+
+    /// Queue the new items of a listing, which lists the newest items first.
+    fn push<C: ListingChild<Item = T>>(&mut self, listing: BasicThing<Listing<C>>) {
+        // Only the first listing that arrives is the backlog, even if earlier polls failed.
+        let skip = !self.skipped;
+        self.polled = true;
+        self.skipped = true;
+
+        let before = self.buffer.len();
+        for item in listing.data.children.into_iter().rev().map(C::into_item) {
+            let new = match (self.id)(&item) {
+                Some(id) => self.seen.insert(id),
+                None => true,
+            };
+
+            if new && !skip {
+                self.buffer.push_back(item);
+            }
+        }
+
+        if skip || self.buffer.len() > before {
+            self.interval = self.options.min_interval;
+        } else {
+            self.back_off();
+        }
+    }
+
+    /// Forget an item that was yielded, so the next poll queues it again.
+    fn forget(&mut self, id: Option<String>) {
+        if let Some(id) = id {
+            self.seen.remove(&id);
+        }
+    }
+
+    /// Wait longer before polling again after a failed poll.
+    fn failed(&mut self) {
+        self.polled = true;
+        self.back_off();
+    }
+
+    fn back_off(&mut self) {
+        self.interval = (self.interval * 2)
+            .min(self.options.max_interval)
+            .max(self.options.min_interval);
+    }
+}
+
+/// A listing followed as new items arrive. See the [module docs](self).
+#[cfg(not(feature = "blocking"))]
+pub struct LiveStream<'a, T> {
+    inner: Pin<Box<dyn Stream<Item = Result<T, RouxError>> + Send + 'a>>,
+}
+
+/// A listing followed as new items arrive. See the [module docs](self).
+#[cfg(feature = "blocking")]
+pub struct LiveStream<'a, T> {
+    inner: Box<dyn Iterator<Item = Result<T, RouxError>> + Send + 'a>,
+}
+
+#[cfg(not(feature = "blocking"))]
+impl<'a, T: Send + 'a> LiveStream<'a, T> {
+    /// Follow the listing `fetch` gets.
+    pub(crate) fn new<C, F, Fut>(poller: Poller<T>, fetch: F) -> LiveStream<'a, T>
+    where
+        C: ListingChild<Item = T>,
+        F: FnMut() -> Fut + Send + 'a,
+        Fut: Future<Output = Result<BasicThing<Listing<C>>, RouxError>> + Send + 'a,
+    {
+        LiveStream::with_ack(poller, fetch, |item| std::future::ready(Ok(item)))
+    }
+
+    /// Follow the listing `fetch` gets, passing each item through `ack` as it is yielded.
+    /// If `ack` fails its error is yielded instead, and the item is forgotten so that the
+    /// next poll yields it again.
+    pub(crate) fn with_ack<C, F, Fut, A, AckFut>(
+        poller: Poller<T>,
+        fetch: F,
+        ack: A,
+    ) -> LiveStream<'a, T>
+    where
+        C: ListingChild<Item = T>,
+        F: FnMut() -> Fut + Send + 'a,
+        Fut: Future<Output = Result<BasicThing<Listing<C>>, RouxError>> + Send + 'a,
+        A: FnMut(T) -> AckFut + Send + 'a,
+        AckFut: Future<Output = Result<T, RouxError>> + Send + 'a,
+    {
+        let inner = futures_util::stream::unfold(
+            (poller, fetch, ack),
+            |(mut poller, mut fetch, mut ack)| async move {
+                loop {
+                    if let Some(item) = poller.pop() {
+                        let id = (poller.id)(&item);
+                        let result = ack(item).await;
+                        if result.is_err() {
+                            poller.forget(id);
+                        }
+                        return Some((result, (poller, fetch, ack)));
+                    }
+
+                    if let Some(wait) = poller.wait() {
+                        client::sleep(wait).await;
+                    }
+
+                    match fetch().await {
+                        Ok(listing) => poller.push(listing),
+                        Err(e) => {
+                            poller.failed();
+                            return Some((Err(e), (poller, fetch, ack)));
+                        }
+                    }
+                }
+            },
+        );
+
+        LiveStream {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+#[cfg(not(feature = "blocking"))]
+impl<T> Stream for LiveStream<'_, T> {
+    type Item = Result<T, RouxError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<'a, T: Send + 'a> LiveStream<'a, T> {
+    /// Follow the listing `fetch` gets.
+    pub(crate) fn new<C, F>(poller: Poller<T>, fetch: F) -> LiveStream<'a, T>
+    where
+        C: ListingChild<Item = T>,
+        F: FnMut() -> Result<BasicThing<Listing<C>>, RouxError> + Send + 'a,
+    {
+        LiveStream::with_ack(poller, fetch, Ok)
+    }
+
+    /// Follow the listing `fetch` gets, passing each item through `ack` as it is yielded.
+    /// If `ack` fails its error is yielded instead, and the item is forgotten so that the
+    /// next poll yields it again.
+    pub(crate) fn with_ack<C, F, A>(
+        mut poller: Poller<T>,
+        mut fetch: F,
+        mut ack: A,
+    ) -> LiveStream<'a, T>
+    where
+        C: ListingChild<Item = T>,
+        F: FnMut() -> Result<BasicThing<Listing<C>>, RouxError> + Send + 'a,
+        A: FnMut(T) -> Result<T, RouxError> + Send + 'a,
+    {
+        let inner = std::iter::from_fn(move || loop {
+            if let Some(item) = poller.pop() {
+                let id = (poller.id)(&item);
+                let result = ack(item);
+                if result.is_err() {
+                    poller.forget(id);
+                }
+                return Some(result);
+            }
+
+            if let Some(wait) = poller.wait() {
+                client::sleep(wait);
+            }
+
+            match fetch() {
+                Ok(listing) => poller.push(listing),
+                Err(e) => {
+                    poller.failed();
+                    return Some(Err(e));
+                }
+            }
+        });
+
+        LiveStream {
+            inner: Box::new(inner),
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<T> Iterator for LiveStream<'_, T> {
+    type Item = Result<T, RouxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Poller, Seen, StreamOptions};
+    use crate::models::response::BasicListing;
+
+    fn page(ids: &[&str]) -> BasicListing<String> {
+        serde_json::from_value(serde_json::json!({
+            "kind": "Listing",
+            "data": {
+                "after": null,
+                "before": null,
+                "children": ids
+                    .iter()
+                    .map(|id| serde_json::json!({"kind": "t3", "data": id}))
+                    .collect::<Vec<_>>(),
+            }
+        }))
+        .unwrap()
+    }
+
+    fn drain(poller: &mut Poller<String>) -> Vec<String> {
+        std::iter::from_fn(|| poller.pop()).collect()
+    }
+
+    fn options() -> StreamOptions {
+        StreamOptions::new()
+            .min_interval(Duration::from_secs(1))
+            .max_interval(Duration::from_secs(4))
+    }
+
+    #[test]
+    fn test_seen() {
+        let mut seen = Seen::new(2);
+        assert!(seen.insert("a".to_owned()));
+        assert!(!seen.insert("a".to_owned()));
+        assert!(seen.insert("b".to_owned()));
+        assert!(seen.insert("c".to_owned()));
+        // `a` was forgotten to make room for `c`.
+        assert!(seen.insert("a".to_owned()));
+
+        seen.remove("c");
+        assert!(seen.insert("c".to_owned()));
+        assert!(!seen.insert("a".to_owned()));
+    }
+
+    #[test]
+    fn test_poller() {
+        let mut poller = Poller::new(options(), |id: &String| Some(id.to_owned()));
+        assert_eq!(poller.wait(), None);
+
+        // Newest first in, oldest first out.
+        poller.push(page(&["b", "a"]));
+        assert_eq!(drain(&mut poller), ["a", "b"]);
+        assert_eq!(poller.wait(), Some(Duration::from_secs(1)));
+
+        poller.push(page(&["c", "b", "a"]));
+        assert_eq!(drain(&mut poller), ["c"]);
+
+        poller.push(page(&["c", "b"]));
+        assert!(drain(&mut poller).is_empty());
+        assert_eq!(poller.wait(), Some(Duration::from_secs(2)));
+
+        poller.failed();
+        poller.push(page(&["c"]));
+        assert_eq!(poller.wait(), Some(Duration::from_secs(4)));
+
+        poller.push(page(&["d", "c"]));
+        assert_eq!(drain(&mut poller), ["d"]);
+        assert_eq!(poller.wait(), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_skip_existing() {
+        let mut poller = Poller::new(options().skip_existing(true), |id: &String| {
+            Some(id.to_owned())
+        });
+
+        poller.push(page(&["b", "a"]));
+        assert!(drain(&mut poller).is_empty());
+
+        poller.push(page(&["c", "b", "a"]));
+        assert_eq!(drain(&mut poller), ["c"]);
+
+        // A failed first poll does not use up the skip.
+        let mut poller = Poller::new(options().skip_existing(true), |id: &String| {
+            Some(id.to_owned())
+        });
+        poller.failed();
+        poller.push(page(&["b", "a"]));
+        assert!(drain(&mut poller).is_empty());
+    }
+}
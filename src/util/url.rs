@@ -1,9 +1,20 @@
+/// Base url for read only Reddit access.
+pub const BASE_URL: &str = "https://www.reddit.com";
+
+/// Base url for OAuth Reddit access.
+pub const OAUTH_URL: &str = "https://oauth.reddit.com";
+
 /// Builds a url for read only Reddit access.
 pub fn build_url(dest: &str) -> String {
-    format!("https://www.reddit.com/{}/.json", dest)
+    build(BASE_URL, dest)
 }
 
 /// Builds a url for OAuth Reddit access.
 pub fn build_oauth(dest: &str) -> String {
-    format!("https://oauth.reddit.com/{}/.json", dest)
+    build(OAUTH_URL, dest)
+}
+
+/// Builds a url for `dest` on `base`, e.g. a local server standing in for Reddit.
+pub fn build(base: &str, dest: &str) -> String {
+    format!("{}/{}/.json", base.trim_end_matches('/'), dest)
 }
@@ -0,0 +1,300 @@
+//! A tiny HTTP server that stands in for Reddit, so tests can run offline.
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A request the server received.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    /// Path and query string.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A canned response.
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn json(status: u16, body: &str) -> Response {
+        Response {
+            status,
+            headers: vec![("Content-Type".to_owned(), "application/json".to_owned())],
+            body: body.to_owned(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Response {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+}
+
+struct Route {
+    method: String,
+    path: String,
+    /// Responses are used in order, the last one is repeated.
+    responses: VecDeque<Response>,
+}
+
+#[derive(Default)]
+struct State {
+    routes: Vec<Route>,
+    requests: Vec<Request>,
+}
+
+pub struct MockServer {
+    pub url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockServer {
+    pub fn start() -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let server_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = server_state.clone();
+                thread::spawn(move || handle(stream, state));
+            }
+        });
+
+        MockServer { url, state }
+    }
+
+    /// Respond to `method` requests for `path` (without the query string) with `responses`.
+    pub fn route(&self, method: &str, path: &str, responses: Vec<Response>) -> &MockServer {
+        self.state.lock().unwrap().routes.push(Route {
+            method: method.to_owned(),
+            path: path.to_owned(),
+            responses: responses.into(),
+        });
+        self
+    }
+
+    /// Every request received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Every request received so far for `path` (without the query string).
+    pub fn requests_to(&self, path: &str) -> Vec<Request> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.path.split('?').next() == Some(path))
+            .collect()
+    }
+}
+
+fn handle(mut stream: TcpStream, state: Arc<Mutex<State>>) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+    let mut line = String::new();
+    if reader.read_line(&mut line).unwrap_or(0) == 0 {
+        return;
+    }
+
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let path = parts.next().unwrap_or_default().to_owned();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_owned(), value.trim().to_owned()));
+        }
+    }
+
+    let length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+
+    let request = Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    };
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(request.clone());
+
+        let route_path = request.path.split('?').next().unwrap_or_default();
+        state
+            .routes
+            .iter_mut()
+            .find(|route| route.method == request.method && route.path == route_path)
+            .map(|route| {
+                if route.responses.len() > 1 {
+                    route.responses.pop_front().unwrap()
+                } else {
+                    route.responses[0].clone()
+                }
+            })
+            .unwrap_or_else(|| Response::json(404, r#"{"message": "Not Found", "error": 404}"#))
+    };
+
+    let mut head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (key, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", key, value));
+    }
+    head.push_str("\r\n");
+
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(response.body.as_bytes());
+}
+
+/// A password grant response.
+pub fn token(access_token: &str, expires_in: u64) -> Response {
+    Response::json(
+        200,
+        &format!(
+            r#"{{"access_token": "{}", "token_type": "bearer", "expires_in": {}, "scope": "*"}}"#,
+            access_token, expires_in
+        ),
+    )
+}
+
+/// A listing of `children`, each a `{"kind": .., "data": ..}` object.
+pub fn listing(children: &[String], after: Option<&str>) -> Response {
+    Response::json(
+        200,
+        &format!(
+            r#"{{"kind": "Listing", "data": {{"after": {}, "before": null, "dist": {}, "modhash": "", "children": [{}]}}}}"#,
+            after
+                .map(|after| format!("\"{}\"", after))
+                .unwrap_or_else(|| "null".to_owned()),
+            children.len(),
+            children.join(",")
+        ),
+    )
+}
+
+/// A submission with the given id, as a listing child.
+pub fn submission(id: &str) -> String {
+    format!(
+        r#"{{"kind": "t3", "data": {{
+            "domain": "self.rust", "banned_by": null, "subreddit": "rust", "selftext_html": null,
+            "selftext": "", "likes": null, "suggested_sort": null, "link_flair_text": null,
+            "id": "{id}", "gilded": 0, "archived": false, "clicked": false, "author": "roux",
+            "score": 1.0, "approved_by": null, "over_18": false, "spoiler": false, "hidden": false,
+            "preview": null, "num_comments": 0, "thumbnail": "self", "subreddit_id": "t5_2s7lj",
+            "hide_score": false, "edited": false, "link_flair_css_class": null,
+            "author_flair_css_class": null, "downs": 0.0, "ups": 1.0, "upvote_ratio": 1.0,
+            "saved": false, "removal_reason": null, "post_hint": null, "stickied": false,
+            "is_self": true, "permalink": "/r/rust/comments/{id}/", "locked": false,
+            "name": "t3_{id}", "created": 1.0, "url": null, "author_flair_text": null,
+            "quarantine": false, "title": "Post {id}", "created_utc": 1.0, "distinguished": null,
+            "visited": false, "num_reports": null
+        }}}}"#,
+        id = id
+    )
+}
+
+/// A comment with the given id, as a listing child.
+pub fn comment(id: &str, parent_id: &str) -> String {
+    format!(
+        r#"{{"kind": "t1", "data": {{
+            "id": "{id}", "name": "t1_{id}", "parent_id": "{parent_id}", "link_id": "t3_post",
+            "author": "roux", "body": "Comment {id}", "subreddit": "rust", "score": 1,
+            "created_utc": 1.0, "replies": ""
+        }}}}"#,
+        id = id,
+        parent_id = parent_id
+    )
+}
+
+/// A stub for comments left out of a listing, as a listing child. No `children` stands for
+/// a "continue this thread" link.
+pub fn more(parent_id: &str, children: &[&str]) -> String {
+    format!(
+        r#"{{"kind": "more", "data": {{
+            "count": {count}, "name": "t1_{id}", "id": "{id}", "parent_id": "{parent_id}",
+            "depth": 0, "children": [{children}]
+        }}}}"#,
+        count = children.len(),
+        id = children.first().unwrap_or(&"_"),
+        parent_id = parent_id,
+        children = children
+            .iter()
+            .map(|id| format!("\"{}\"", id))
+            .collect::<Vec<_>>()
+            .join(",")
+    )
+}
+
+/// The `[post, comments]` pair Reddit answers `/comments/{id}` with.
+pub fn article(post: &str, comments: &[String]) -> Response {
+    Response::json(
+        200,
+        &format!(
+            "[{}, {}]",
+            listing(&[submission(post)], None).body,
+            listing(comments, None).body
+        ),
+    )
+}
+
+/// An `api_type=json` response listing `things`.
+pub fn things(things: &[String]) -> Response {
+    Response::json(
+        200,
+        &format!(
+            r#"{{"json": {{"errors": [], "data": {{"things": [{}]}}}}}}"#,
+            things.join(",")
+        ),
+    )
+}
+
+/// An inbox item with the given id, as a listing child.
+pub fn message(id: &str, ty: &str, was_comment: bool) -> String {
+    format!(
+        r#"{{"kind": "{kind}", "data": {{
+            "id": "{id}", "name": "{kind}_{id}", "subject": "Subject {id}",
+            "was_comment": {was_comment}, "author": "roux", "parent_id": null,
+            "subreddit_name_prefixed": null, "new": true, "type": "{ty}", "body": "Body {id}",
+            "dest": "USERNAME", "body_html": "", "created": 1.0, "created_utc": 1.0, "context": ""
+        }}}}"#,
+        kind = if was_comment { "t1" } else { "t4" },
+        id = id,
+        ty = ty,
+        was_comment = was_comment
+    )
+}
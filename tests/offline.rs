@@ -0,0 +1,1308 @@
+//! Tests that run against a local server standing in for Reddit.
+
+extern crate roux;
+
+mod mock;
+
+use mock::{
+    article, comment, listing, message, more, submission, things, token, MockServer, Response,
+};
+use std::time::{Duration, Instant};
+
+use roux::me::media::Media;
+use roux::me::VoteDirection;
+use roux::util::{
+    ArticleOption, CommentSort, FeedOption, Fullname, InboxOptions, ListingItem, MemoryTokenStore,
+    Paginator, RetryPolicy, RouxError, SortType, StreamOptions,
+};
+use roux::{GalleryBuilder, GalleryItem, Reddit, SubmissionBuilder, Subreddit, Subreddits, User};
+
+static USER_AGENT: &str = "linux:roux-tests:v0.0.0 (by /u/roux)";
+
+fn reddit(server: &MockServer) -> Reddit {
+    Reddit::new(USER_AGENT, "CLIENT_ID", "CLIENT_SECRET")
+        .base_url(&server.url)
+        .oauth_url(&server.url)
+        .username("USERNAME")
+        .password("PASSWORD")
+}
+
+fn me_data() -> Response {
+    Response::json(
+        200,
+        r#"{"id": "1", "name": "USERNAME", "is_employee": false, "verified": true,
+            "over_18": false, "has_verified_email": true, "is_suspended": false,
+            "has_mail": false, "inbox_count": 0, "created": 1.0, "created_utc": 1.0,
+            "in_beta": false, "comment_karma": 1, "link_karma": 1, "is_mod": false,
+            "is_gold": false, "icon_img": ""}"#,
+    )
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_login_with_base_url() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route("GET", "/api/v1/me/.json", vec![me_data()]);
+
+    let me = reddit(&server).login().await.unwrap();
+    let me_data = me.me().await.unwrap();
+    assert_eq!(me_data.name, "USERNAME");
+
+    let requests = server.requests_to("/api/v1/access_token/.json");
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].body.contains("grant_type=password"));
+
+    let requests = server.requests_to("/api/v1/me/.json");
+    assert_eq!(requests[0].header("authorization"), Some("Bearer first"));
+    assert_eq!(requests[0].header("user-agent"), Some(USER_AGENT));
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_saved_without_identity() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![Response::json(
+                200,
+                r#"{"access_token": "first", "token_type": "bearer", "expires_in": 3600,
+                    "scope": "history"}"#,
+            )],
+        )
+        .route("GET", "/api/v1/me/.json", vec![me_data()])
+        .route(
+            "GET",
+            "/user/USERNAME/saved/.json",
+            vec![listing(&[submission("a")], None)],
+        );
+
+    // Without the `identity` scope the login does not learn the username, so it is looked
+    // up when a listing needs it.
+    let me = Reddit::new(USER_AGENT, "CLIENT_ID", "CLIENT_SECRET")
+        .base_url(&server.url)
+        .oauth_url(&server.url)
+        .refresh_token("REFRESH_TOKEN")
+        .login()
+        .await
+        .unwrap();
+    let saved = me.saved(None).await.unwrap();
+    assert_eq!(saved.data.children.len(), 1);
+    assert_eq!(server.requests_to("/api/v1/me/.json").len(), 1);
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_token_store_keeps_logins_apart() {
+    let server = MockServer::start();
+    server.route(
+        "POST",
+        "/api/v1/access_token/.json",
+        vec![token("app", 3600), token("user", 3600)],
+    );
+
+    let reddit = reddit(&server).token_store(MemoryTokenStore::new());
+
+    let app = reddit.clone().app_only().await.unwrap();
+    assert_eq!(app.access_token().as_deref(), Some("app"));
+
+    // The app token is in the store, but a user login must not pick it up.
+    let me = reddit.clone().login().await.unwrap();
+    assert_eq!(me.access_token().as_deref(), Some("user"));
+
+    // Both are restored from the store without asking Reddit again.
+    let app = reddit.clone().app_only().await.unwrap();
+    assert_eq!(app.access_token().as_deref(), Some("app"));
+    let me = reddit.login().await.unwrap();
+    assert_eq!(me.access_token().as_deref(), Some("user"));
+
+    let requests = server.requests_to("/api/v1/access_token/.json");
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].body.contains("grant_type=client_credentials"));
+    assert!(requests[1].body.contains("grant_type=password"));
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_app_only() {
+    for (device_id, grant) in [
+        (None, "grant_type=client_credentials".to_owned()),
+        (
+            Some("DEVICE_ID"),
+            format!(
+                "grant_type={}&device_id=DEVICE_ID",
+                form_url("https://oauth.reddit.com/grants/installed_client")
+            ),
+        ),
+    ] {
+        // Tokens come from the base url, everything else from the OAuth one.
+        let auth_server = MockServer::start();
+        auth_server.route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("app", 3600)],
+        );
+        let server = MockServer::start();
+        server
+            .route(
+                "GET",
+                "/r/rust/hot.json",
+                vec![listing(&[submission("a")], None)],
+            )
+            .route(
+                "GET",
+                "/user/spez/submitted/.json",
+                vec![listing(&[submission("b")], None)],
+            )
+            .route("GET", "/subreddits/search.json", vec![listing(&[], None)]);
+
+        let mut reddit = Reddit::new(USER_AGENT, "CLIENT_ID", "CLIENT_SECRET")
+            .base_url(&auth_server.url)
+            .oauth_url(&server.url);
+        if let Some(device_id) = device_id {
+            reddit = reddit.device_id(device_id);
+        }
+        let app = reddit.app_only().await.unwrap();
+
+        let hot = app.subreddit("rust").hot(25, None).await.unwrap();
+        assert_eq!(hot.data.children[0].data.id, "a");
+        let submitted = app.user("spez").submitted(None).await.unwrap();
+        assert_eq!(submitted.data.children[0].data.id, "b");
+        let subreddits = app.search_subreddits("rust", Some(5), None).await;
+        assert!(subreddits.is_ok());
+
+        let requests = auth_server.requests_to("/api/v1/access_token/.json");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].body, grant);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        for request in requests {
+            assert_eq!(request.header("authorization"), Some("Bearer app"));
+        }
+    }
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_read_only_base_url() {
+    let server = MockServer::start();
+    server
+        .route(
+            "GET",
+            "/r/rust/hot.json",
+            vec![listing(&[submission("a"), submission("b")], None)],
+        )
+        .route(
+            "GET",
+            "/user/roux/submitted/.json",
+            vec![listing(&[submission("c")], None)],
+        )
+        .route(
+            "GET",
+            "/subreddits/search.json",
+            vec![Response::json(
+                200,
+                r#"{"kind": "Listing", "data": {"after": null, "before": null, "dist": 0,
+                    "modhash": "", "children": []}}"#,
+            )],
+        );
+
+    let hot = Subreddit::new("rust")
+        .base_url(&server.url)
+        .hot(25, None)
+        .await
+        .unwrap();
+    assert_eq!(hot.data.children.len(), 2);
+    assert_eq!(hot.data.children[1].data.id, "b");
+
+    let submitted = User::new("roux")
+        .base_url(&server.url)
+        .submitted(None)
+        .await
+        .unwrap();
+    assert_eq!(submitted.data.children[0].data.id, "c");
+
+    let subreddits = Subreddits::search_with_base(&server.url, "rust", Some(3), None)
+        .await
+        .unwrap();
+    assert!(subreddits.data.children.is_empty());
+    assert_eq!(
+        server.requests_to("/subreddits/search.json")[0].path,
+        "/subreddits/search.json?q=rust&limit=3"
+    );
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_refresh_on_unauthorized() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600), token("second", 3600)],
+        )
+        .route(
+            "GET",
+            "/api/v1/me/.json",
+            vec![
+                Response::json(401, r#"{"message": "Unauthorized", "error": 401}"#),
+                me_data(),
+            ],
+        );
+
+    let me = reddit(&server).login().await.unwrap();
+    assert_eq!(me.access_token().as_deref(), Some("first"));
+    let me_data = me.me().await;
+    assert!(me_data.is_ok());
+    assert_eq!(me.access_token().as_deref(), Some("second"));
+
+    let requests = server.requests_to("/api/v1/me/.json");
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].header("authorization"), Some("Bearer first"));
+    assert_eq!(requests[1].header("authorization"), Some("Bearer second"));
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_refresh_before_expiry() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 10), token("second", 3600)],
+        )
+        .route("GET", "/api/v1/me/.json", vec![me_data()]);
+
+    let me = reddit(&server).login().await.unwrap();
+    let clone = me.clone();
+
+    // The first token expires within the refresh margin, so it is renewed before use,
+    // and the clone sees the renewed token.
+    let me_data = me.me().await;
+    assert!(me_data.is_ok());
+    let clone_data = clone.me().await;
+    assert!(clone_data.is_ok());
+
+    assert_eq!(server.requests_to("/api/v1/access_token/.json").len(), 2);
+    let requests = server.requests_to("/api/v1/me/.json");
+    assert_eq!(requests[0].header("authorization"), Some("Bearer second"));
+    assert_eq!(requests[1].header("authorization"), Some("Bearer second"));
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_otp_used_once() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route(
+            "GET",
+            "/api/v1/me/.json",
+            vec![Response::json(
+                401,
+                r#"{"message": "Unauthorized", "error": 401}"#,
+            )],
+        );
+
+    let me = reddit(&server).otp("123456").login().await.unwrap();
+
+    // Without a refresh token the expired login cannot be renewed: the code is spent.
+    let me_data = me.me().await;
+    assert!(matches!(me_data, Err(RouxError::Unauthorized(_))));
+
+    let requests = server.requests_to("/api/v1/access_token/.json");
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].body.contains("password=PASSWORD%3A123456"));
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_rate_limit_wait() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route(
+            "GET",
+            "/api/v1/me/.json",
+            vec![me_data()
+                .header("X-Ratelimit-Remaining", "0")
+                .header("X-Ratelimit-Used", "600")
+                .header("X-Ratelimit-Reset", "1")],
+        );
+
+    let me = reddit(&server).login().await.unwrap();
+    let first = me.me().await;
+    assert!(first.is_ok());
+
+    let rate_limit = me.rate_limit().unwrap();
+    assert_eq!(rate_limit.remaining, 0.0);
+    assert_eq!(rate_limit.used, 600.0);
+
+    // The budget is used up, so the next request waits for the window to reset.
+    let start = Instant::now();
+    let second = me.me().await;
+    assert!(second.is_ok());
+    assert!(start.elapsed() >= Duration::from_millis(900));
+}
+
+fn retry_policy() -> RetryPolicy {
+    RetryPolicy::new()
+        .max_attempts(3)
+        .base_delay(Duration::from_millis(10))
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_retry_server_error() {
+    let server = MockServer::start();
+    server.route(
+        "GET",
+        "/r/rust/hot.json",
+        vec![
+            Response::json(503, r#"{"message": "Service Unavailable", "error": 503}"#),
+            Response::json(502, r#"{"message": "Bad Gateway", "error": 502}"#)
+                .header("Retry-After", "0"),
+            listing(&[submission("a")], None),
+        ],
+    );
+
+    let hot = Subreddit::new("rust")
+        .base_url(&server.url)
+        .retry_policy(retry_policy())
+        .hot(25, None)
+        .await
+        .unwrap();
+    assert_eq!(hot.data.children[0].data.id, "a");
+    assert_eq!(server.requests_to("/r/rust/hot.json").len(), 3);
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_retry_gives_up() {
+    let server = MockServer::start();
+    server.route(
+        "GET",
+        "/user/roux/about/.json",
+        vec![Response::json(
+            500,
+            r#"{"message": "Internal Server Error", "error": 500}"#,
+        )],
+    );
+
+    let about = User::new("roux")
+        .base_url(&server.url)
+        .retry_policy(retry_policy())
+        .about(None)
+        .await;
+    assert!(matches!(about, Err(RouxError::ServerError(_))));
+    assert_eq!(server.requests_to("/user/roux/about/.json").len(), 3);
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_no_retry_for_post() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route(
+            "POST",
+            "/api/comment/.json",
+            vec![Response::json(
+                503,
+                r#"{"message": "Service Unavailable", "error": 503}"#,
+            )],
+        );
+
+    let me = reddit(&server)
+        .retry_policy(retry_policy())
+        .login()
+        .await
+        .unwrap();
+    let comment = me.comment("Hello", &"t3_a".parse().unwrap()).await;
+    assert!(comment.is_err());
+    assert_eq!(server.requests_to("/api/comment/.json").len(), 1);
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_api_errors() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route(
+            "POST",
+            "/api/submit/.json",
+            vec![
+                Response::json(
+                    200,
+                    r#"{"json": {"errors": [
+                        ["SUBREDDIT_NOEXIST", "that subreddit doesn't exist", "sr"],
+                        ["RATELIMIT", "you are doing that too much. try again in 3 minutes.", "ratelimit"]
+                    ]}}"#,
+                ),
+                Response::json(
+                    200,
+                    r#"{"json": {"errors": [], "data": {"url": "https://www.reddit.com/r/rust/comments/abc/",
+                        "drafts_count": 0, "id": "abc", "name": "t3_abc"}}}"#,
+                ),
+            ],
+        )
+        .route(
+            "POST",
+            "/api/comment/.json",
+            vec![Response::json(
+                200,
+                &format!(
+                    r#"{{"json": {{"errors": [], "data": {{"things": [{}]}}}}}}"#,
+                    comment("def", "t3_abc")
+                ),
+            )],
+        );
+
+    let me = reddit(&server).login().await.unwrap();
+
+    let submitted = me.submit_text("Title", "Text", "nope").await;
+    match submitted {
+        Err(RouxError::Api(errors)) => {
+            assert_eq!(errors.len(), 2);
+            assert_eq!(errors[0].code, "SUBREDDIT_NOEXIST");
+            assert_eq!(errors[0].field.as_deref(), Some("sr"));
+            assert_eq!(errors[1].retry_after(), Some(Duration::from_secs(180)));
+        }
+        other => panic!("expected API errors, got {:?}", other),
+    }
+
+    let submitted = me.submit_text("Title", "Text", "rust").await.unwrap();
+    assert_eq!(submitted.name, "t3_abc");
+
+    let comment = me
+        .comment("Hello", &"t3_abc".parse().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(comment.name.as_deref(), Some("t1_def"));
+
+    let requests = server.requests_to("/api/comment/.json");
+    assert!(requests[0].body.contains("api_type=json"));
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_status_errors() {
+    let server = MockServer::start();
+    server
+        .route(
+            "GET",
+            "/r/secret/hot.json",
+            vec![Response::json(
+                403,
+                r#"{"reason": "private", "message": "Forbidden", "error": 403}"#,
+            )],
+        )
+        .route(
+            "GET",
+            "/r/busy/hot.json",
+            vec![
+                Response::json(429, r#"{"message": "Too Many Requests", "error": 429}"#)
+                    .header("X-Ratelimit-Reset", "42"),
+            ],
+        );
+
+    let forbidden = Subreddit::new("secret")
+        .base_url(&server.url)
+        .hot(25, None)
+        .await;
+    match forbidden {
+        Err(RouxError::Forbidden(error)) => {
+            assert_eq!(error.status, 403);
+            assert_eq!(error.reason.as_deref(), Some("private"));
+            assert!(error.url.ends_with("/r/secret/hot.json?limit=25"));
+            assert!(error.body.contains("Forbidden"));
+        }
+        other => panic!("expected Forbidden, got {:?}", other),
+    }
+
+    let not_found = Subreddit::new("missing")
+        .base_url(&server.url)
+        .hot(25, None)
+        .await;
+    assert!(matches!(not_found, Err(RouxError::NotFound(_))));
+
+    let rate_limited = Subreddit::new("busy")
+        .base_url(&server.url)
+        .retry_policy(RetryPolicy::none())
+        .hot(25, None)
+        .await;
+    match rate_limited {
+        Err(RouxError::RateLimited(error, reset)) => {
+            assert_eq!(error.status, 429);
+            assert_eq!(reset, Some(Duration::from_secs(42)));
+        }
+        other => panic!("expected RateLimited, got {:?}", other),
+    }
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_feed_options() {
+    let server = MockServer::start();
+    server.route(
+        "GET",
+        "/user/spez/submitted/.json",
+        vec![listing(&[], None)],
+    );
+    let user = User::new("spez").base_url(&server.url);
+
+    let options = FeedOption::new()
+        .sort(SortType::New)
+        .raw_json()
+        .param("sr", "r/rust");
+    user.submitted(Some(options)).await.unwrap();
+
+    let requests = server.requests_to("/user/spez/submitted/.json");
+    assert!(requests[0]
+        .path
+        .ends_with("?&sort=new&raw_json=1&sr=r%2Frust&"));
+
+    // Invalid options fail before anything is sent.
+    let invalid = user.submitted(Some(FeedOption::new().limit(500))).await;
+    assert!(matches!(invalid, Err(RouxError::InvalidOption(_))));
+    assert_eq!(server.requests_to("/user/spez/submitted/.json").len(), 1);
+
+    server
+        .route("GET", "/r/rust/hot.json", vec![listing(&[], None)])
+        .route("GET", "/subreddits/search.json", vec![listing(&[], None)]);
+    let subreddit = Subreddit::new("rust").base_url(&server.url);
+
+    // The limit of the options replaces the positional one.
+    subreddit
+        .hot(25, Some(FeedOption::new().limit(5)))
+        .await
+        .unwrap();
+    let invalid = subreddit.hot(0, None).await;
+    assert!(matches!(invalid, Err(RouxError::InvalidOption(_))));
+    let requests = server.requests_to("/r/rust/hot.json");
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].path.ends_with("/r/rust/hot.json?&limit=5&"));
+
+    Subreddits::search_with_base(&server.url, "rust & go", Some(3), None)
+        .await
+        .unwrap();
+    let invalid = Subreddits::search_with_base(&server.url, "rust", Some(101), None).await;
+    assert!(matches!(invalid, Err(RouxError::InvalidOption(_))));
+    let requests = server.requests_to("/subreddits/search.json");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].path,
+        "/subreddits/search.json?q=rust+%26+go&limit=3"
+    );
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_comment_tree() {
+    let server = MockServer::start();
+
+    // 150 top level comments are left out, and one of them continues in a sub-thread.
+    let left_out: Vec<String> = (0..150).map(|i| format!("m{}", i)).collect();
+    let left_out: Vec<&str> = left_out.iter().map(String::as_str).collect();
+
+    let batch =
+        |ids: &[&str]| -> Vec<String> { ids.iter().map(|id| comment(id, "t3_post")).collect() };
+    let mut first = batch(&left_out[..100]);
+    first.push(more("t1_m0", &[]));
+
+    server
+        .route(
+            "GET",
+            "/r/rust/comments/post.json",
+            vec![article(
+                "post",
+                &[comment("a", "t3_post"), more("t3_post", &left_out)],
+            )],
+        )
+        .route(
+            "GET",
+            "/api/morechildren.json",
+            vec![things(&first), things(&batch(&left_out[100..]))],
+        )
+        .route(
+            "GET",
+            "/r/rust/comments/post/_/m0.json",
+            vec![article(
+                "post",
+                &[comment("m0", "t3_post"), comment("deep", "t1_m0")],
+            )],
+        );
+
+    let tree = Subreddit::new("rust")
+        .base_url(&server.url)
+        .comment_tree("post")
+        .await
+        .unwrap();
+    assert_eq!(tree.len(), 152);
+
+    let deep = tree.get(&"t1_deep".parse().unwrap()).unwrap();
+    assert_eq!(deep.depth(), 1);
+    assert_eq!(deep.parent().unwrap().comment().id.as_deref(), Some("m0"));
+
+    let order: Vec<String> = tree
+        .depth_first()
+        .take(3)
+        .map(|node| node.comment().id.to_owned().unwrap())
+        .collect();
+    assert_eq!(order, ["a", "m0", "deep"]);
+
+    let requests = server.requests_to("/api/morechildren.json");
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].path.contains("link_id=t3_post&children=m0,m1,"));
+    assert!(requests[1].path.contains("children=m100,"));
+    assert_eq!(
+        server.requests_to("/r/rust/comments/post/_/m0.json").len(),
+        1
+    );
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_article() {
+    let server = MockServer::start();
+    let post = article("post", &[comment("a", "t3_post"), comment("b", "t3_post")]);
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route("GET", "/r/rust/comments/post.json", vec![post.to_owned()])
+        .route("GET", "/comments/post/.json", vec![post]);
+
+    let options = ArticleOption::new()
+        .sort(CommentSort::New)
+        .comment("a")
+        .context(2);
+    let article = Subreddit::new("rust")
+        .base_url(&server.url)
+        .article("post", Some(options))
+        .await
+        .unwrap();
+    assert_eq!(article.submission.title, "Post post");
+    assert_eq!(article.comments.data.children.len(), 2);
+
+    let requests = server.requests_to("/r/rust/comments/post.json");
+    assert!(requests[0].path.ends_with("?&sort=new&context=2&comment=a"));
+
+    let me = reddit(&server).login().await.unwrap();
+    let article = me.article("post", None).await.unwrap();
+    assert_eq!(article.submission.id, "post");
+    assert_eq!(server.requests_to("/comments/post/.json").len(), 1);
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_actions() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route("POST", "/api/vote/.json", vec![Response::json(200, "{}")])
+        .route("POST", "/api/save/.json", vec![Response::json(200, "{}")])
+        .route("POST", "/api/hide/.json", vec![Response::json(200, "{}")])
+        .route(
+            "POST",
+            "/api/report/.json",
+            vec![Response::json(
+                200,
+                r#"{"json": {"errors": [["SUBREDDIT_RULE_INVALID", "invalid rule", "rule_reason"]]}}"#,
+            )],
+        );
+
+    let me = reddit(&server).login().await.unwrap();
+    let post: Fullname = "t3_a".parse().unwrap();
+
+    me.vote(&post, VoteDirection::Down).await.unwrap();
+    me.save(&post, Some("rust")).await.unwrap();
+    me.hide(&[post.to_owned(), "t3_b".parse().unwrap()])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        server.requests_to("/api/vote/.json")[0].body,
+        "id=t3_a&dir=-1"
+    );
+    assert_eq!(
+        server.requests_to("/api/save/.json")[0].body,
+        "id=t3_a&category=rust"
+    );
+    assert_eq!(
+        server.requests_to("/api/hide/.json")[0].body,
+        "id=t3_a%2Ct3_b"
+    );
+
+    let report = me.report(&post, "spam", Some("No spam")).await;
+    match report {
+        Err(RouxError::Api(errors)) => assert_eq!(errors[0].code, "SUBREDDIT_RULE_INVALID"),
+        other => panic!("expected Api, got {:?}", other),
+    }
+    assert!(server.requests_to("/api/report/.json")[0]
+        .body
+        .contains("thing_id=t3_a&reason=spam&rule_reason=No+spam"));
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_manage_posts() {
+    let server = MockServer::start();
+    let ok = || vec![Response::json(200, "{}")];
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route("POST", "/api/del/.json", ok())
+        .route("POST", "/api/marknsfw/.json", ok())
+        .route("POST", "/api/sendreplies/.json", ok())
+        .route(
+            "POST",
+            "/api/set_suggested_sort/.json",
+            vec![Response::json(200, r#"{"json": {"errors": []}}"#)],
+        );
+
+    let me = reddit(&server).login().await.unwrap();
+    let post: Fullname = "t3_a".parse().unwrap();
+
+    me.delete(&post).await.unwrap();
+    me.mark_nsfw(&post).await.unwrap();
+    me.send_replies(&post, false).await.unwrap();
+    me.set_suggested_sort(&post, Some(CommentSort::Qa))
+        .await
+        .unwrap();
+    me.set_suggested_sort(&post, None).await.unwrap();
+
+    assert_eq!(server.requests_to("/api/del/.json")[0].body, "id=t3_a");
+    assert_eq!(server.requests_to("/api/marknsfw/.json")[0].body, "id=t3_a");
+    assert_eq!(
+        server.requests_to("/api/sendreplies/.json")[0].body,
+        "id=t3_a&state=false"
+    );
+
+    let requests = server.requests_to("/api/set_suggested_sort/.json");
+    assert_eq!(requests[0].body, "api_type=json&id=t3_a&sort=qa");
+    assert_eq!(requests[1].body, "api_type=json&id=t3_a&sort=");
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_submit() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route(
+            "POST",
+            "/api/submit/.json",
+            vec![Response::json(
+                200,
+                r#"{"json": {"errors": [], "data": {"url": "https://www.reddit.com/r/rust/comments/abc/",
+                    "drafts_count": 0, "id": "abc", "name": "t3_abc"}}}"#,
+            )],
+        );
+
+    let me = reddit(&server).login().await.unwrap();
+
+    let post = SubmissionBuilder::link("rust", "Hello world", "https://example.com/a,b")
+        .flair_id("f1")
+        .nsfw(true)
+        .send_replies(false)
+        .resubmit(false);
+    let submitted = me.submit(post).await.unwrap();
+    assert_eq!(submitted.id, "abc");
+    assert_eq!(submitted.name, "t3_abc");
+    assert_eq!(submitted.url, "https://www.reddit.com/r/rust/comments/abc/");
+
+    assert_eq!(
+        server.requests_to("/api/submit/.json")[0].body,
+        "api_type=json&kind=link&sr=rust&title=Hello+world&url=https%3A%2F%2Fexample.com%2Fa%2Cb\
+         &flair_id=f1&nsfw=true&spoiler=false&sendreplies=false&resubmit=false"
+    );
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_submit_image() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route(
+            "POST",
+            "/api/media/asset/.json",
+            vec![Response::json(
+                200,
+                r#"{"args": {"action": "//reddit-uploaded-media.s3-accelerate.amazonaws.com",
+                    "fields": [{"name": "key", "value": "rte_images/asset1"},
+                        {"name": "acl", "value": "private"}]},
+                    "asset": {"asset_id": "asset1", "processing_state": "incomplete",
+                        "websocket_url": "wss://example.com/asset1"}}"#,
+            )],
+        )
+        .route("POST", "/upload", vec![Response::json(201, "")])
+        .route(
+            "POST",
+            "/api/submit/.json",
+            vec![Response::json(
+                200,
+                r#"{"json": {"errors": [], "data": {"websocket_url": "wss://example.com/x",
+                    "user_submitted_page": "https://www.reddit.com/user/USERNAME/submitted/"}}}"#,
+            )],
+        )
+        .route(
+            "GET",
+            "/user/USERNAME/submitted/.json",
+            vec![
+                listing(&[submission("old")], None),
+                listing(&[submission("new"), submission("old")], None),
+            ],
+        );
+
+    let me = reddit(&server)
+        .upload_url(&format!("{}/upload", server.url))
+        .login()
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        Media::from_bytes("ferris.png", b"GIF89a".to_vec()),
+        Err(RouxError::InvalidMedia(_))
+    ));
+
+    let image = Media::from_bytes("ferris.gif", b"GIF89a".to_vec()).unwrap();
+    let submitted = me.submit_image("Post new", image, "rust").await.unwrap();
+    assert_eq!(submitted.name, "t3_new");
+    assert_eq!(
+        submitted.url,
+        format!("{}/r/rust/comments/new/", server.url)
+    );
+
+    assert_eq!(
+        server.requests_to("/api/media/asset/.json")[0].body,
+        "filepath=ferris.gif&mimetype=image%2Fgif"
+    );
+
+    let upload = &server.requests_to("/upload")[0];
+    assert!(upload.header("authorization").is_none());
+    assert!(upload
+        .header("content-type")
+        .unwrap()
+        .starts_with("multipart/form-data; boundary="));
+    assert!(upload.body.contains("rte_images/asset1"));
+    assert!(upload
+        .body
+        .contains("Content-Type: image/gif\r\n\r\nGIF89a\r\n"));
+
+    let submit = &server.requests_to("/api/submit/.json")[0].body;
+    assert!(submit.contains("kind=image"));
+    assert!(submit.contains(&format!(
+        "url={}",
+        form_url(&format!("{}/upload/rte_images/asset1", server.url))
+    )));
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_submit_gallery() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route(
+            "POST",
+            "/api/media/asset/.json",
+            ["m1", "m2"]
+                .iter()
+                .map(|id| {
+                    Response::json(
+                        200,
+                        &format!(
+                            r#"{{"args": {{"action": "//example.com", "fields": [
+                                {{"name": "key", "value": "rte_images/{id}"}}]}},
+                                "asset": {{"asset_id": "{id}"}}}}"#,
+                            id = id
+                        ),
+                    )
+                })
+                .collect(),
+        )
+        .route("POST", "/upload", vec![Response::json(201, "")])
+        .route(
+            "POST",
+            "/api/submit_gallery_post/.json",
+            vec![Response::json(
+                200,
+                r#"{"json": {"errors": [], "data": {
+                    "url": "https://www.reddit.com/r/rust/comments/gal/title/"}}}"#,
+            )],
+        )
+        .route(
+            "GET",
+            "/r/rust/new.json",
+            vec![listing(
+                &[submission("gal").replace(
+                    r#""num_reports": null"#,
+                    r#""num_reports": null, "is_gallery": true,
+                        "gallery_data": {"items": [
+                            {"media_id": "m1", "id": 1, "caption": "One"},
+                            {"media_id": "m2", "id": 2}]},
+                        "media_metadata": {
+                            "m1": {"status": "valid", "e": "Image", "m": "image/gif", "id": "m1",
+                                "s": {"u": "https://i.redd.it/m1.gif", "x": 1, "y": 1},
+                                "p": [{"u": "https://preview.redd.it/m1.gif", "x": 1, "y": 1}]},
+                            "m2": {"status": "unprocessed"}}"#,
+                )],
+                None,
+            )],
+        );
+
+    let me = reddit(&server)
+        .upload_url(&format!("{}/upload", server.url))
+        .login()
+        .await
+        .unwrap();
+
+    let image = |name: &str| Media::from_bytes(name, b"GIF89a".to_vec()).unwrap();
+    let gallery = GalleryBuilder::new("rust", "Gallery")
+        .item(GalleryItem::new(image("a.gif")).caption("One"))
+        .item(GalleryItem::new(image("b.gif")).outbound_url("https://example.com"));
+
+    let submitted = me.submit_gallery(gallery).await.unwrap();
+    assert_eq!(submitted.name, "t3_gal");
+    assert_eq!(server.requests_to("/upload").len(), 2);
+
+    let request = &server.requests_to("/api/submit_gallery_post/.json")[0];
+    assert_eq!(request.header("content-type"), Some("application/json"));
+    let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(body["items"][0]["media_id"], "m1");
+    assert_eq!(body["items"][1]["outbound_url"], "https://example.com");
+
+    let latest = Subreddit::new("rust")
+        .base_url(&server.url)
+        .latest(25, None)
+        .await
+        .unwrap();
+    let post = &latest.data.children[0].data;
+    assert_eq!(post.is_gallery, Some(true));
+
+    let items = &post.gallery_data.as_ref().unwrap().items;
+    assert_eq!(items[0].caption.as_deref(), Some("One"));
+    assert_eq!(items[1].caption, None);
+
+    let media = post.media_metadata.as_ref().unwrap();
+    assert_eq!(
+        media["m1"].source.as_ref().unwrap().url.as_deref(),
+        Some("https://i.redd.it/m1.gif")
+    );
+    assert_eq!(media["m1"].previews.len(), 1);
+    assert!(media["m2"].source.is_none());
+}
+
+/// `s` encoded the way forms encode it.
+fn form_url(s: &str) -> String {
+    s.replace(':', "%3A").replace('/', "%2F")
+}
+
+/// The next item of a paginator or live stream.
+#[cfg(not(feature = "blocking"))]
+async fn next<S: futures::Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+    futures::StreamExt::next(stream).await
+}
+
+/// The next item of a paginator or live stream.
+#[cfg(feature = "blocking")]
+fn next<I: Iterator>(iter: &mut I) -> Option<I::Item> {
+    iter.next()
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_paginator() {
+    let server = MockServer::start();
+    server.route(
+        "GET",
+        "/r/rust/new.json",
+        vec![
+            listing(&[submission("a"), submission("b")], Some("t3_b")),
+            listing(&[submission("c"), submission("d")], Some("t3_d")),
+            listing(&[], Some("t3_d")),
+        ],
+    );
+    let subreddit = Subreddit::new("rust").base_url(&server.url);
+
+    let mut posts = Paginator::new(|options| subreddit.latest(2, Some(options)));
+    let mut ids = Vec::new();
+    while let Some(post) = next(&mut posts).await {
+        ids.push(post.unwrap().id);
+    }
+    assert_eq!(ids, ["a", "b", "c", "d"]);
+
+    // The empty third page ends the listing.
+    let requests = server.requests_to("/r/rust/new.json");
+    assert_eq!(requests.len(), 3);
+    assert!(requests[1].path.contains("after=t3_b&count=2"));
+    assert!(requests[2].path.contains("after=t3_d&count=4"));
+
+    let server = MockServer::start();
+    server.route(
+        "GET",
+        "/r/rust/new.json",
+        vec![
+            listing(&[submission("a"), submission("b")], Some("t3_b")),
+            listing(&[submission("c"), submission("d")], Some("t3_d")),
+        ],
+    );
+    let subreddit = Subreddit::new("rust").base_url(&server.url);
+
+    let mut posts = Paginator::new(|options| subreddit.latest(2, Some(options))).max_items(3);
+    let mut ids = Vec::new();
+    while let Some(post) = next(&mut posts).await {
+        ids.push(post.unwrap().id);
+    }
+    assert_eq!(ids, ["a", "b", "c"]);
+    assert_eq!(server.requests_to("/r/rust/new.json").len(), 2);
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_paginator_resume() {
+    let server = MockServer::start();
+    server.route(
+        "GET",
+        "/r/rust/new.json",
+        vec![
+            listing(&[submission("a"), submission("b")], Some("t3_b")),
+            listing(&[submission("c"), submission("d")], Some("t3_d")),
+        ],
+    );
+    let subreddit = Subreddit::new("rust").base_url(&server.url);
+
+    let mut posts = Paginator::new(|options| subreddit.latest(2, Some(options)));
+    for _ in 0..3 {
+        next(&mut posts).await.unwrap().unwrap();
+    }
+    let cursor = serde_json::to_string(&posts.cursor()).unwrap();
+
+    let server = MockServer::start();
+    // `c` shows up again, as if a post above it had been removed in the meantime.
+    server.route(
+        "GET",
+        "/r/rust/new.json",
+        vec![listing(&[submission("c"), submission("d")], None)],
+    );
+    let subreddit = Subreddit::new("rust").base_url(&server.url);
+
+    let mut posts = Paginator::new(|options| subreddit.latest(2, Some(options)))
+        .resume(serde_json::from_str(&cursor).unwrap());
+    let mut ids = Vec::new();
+    while let Some(post) = next(&mut posts).await {
+        ids.push(post.unwrap().id);
+    }
+    assert_eq!(ids, ["d"]);
+
+    let requests = server.requests_to("/r/rust/new.json");
+    assert!(requests[0].path.contains("after=t3_c&count=3"));
+
+    let server = MockServer::start();
+    server.route("GET", "/r/rust/new.json", vec![listing(&[], None)]);
+    let subreddit = Subreddit::new("rust").base_url(&server.url);
+
+    let mut posts = Paginator::new(|options| subreddit.latest(2, Some(options)))
+        .resume(serde_json::from_str(&cursor).unwrap());
+    let result = next(&mut posts).await.unwrap();
+    assert!(matches!(result, Err(RouxError::StaleCursor(ref anchor)) if anchor == "t3_c"));
+    let end = next(&mut posts).await;
+    assert!(end.is_none());
+}
+
+fn stream_options() -> StreamOptions {
+    StreamOptions::new()
+        .min_interval(Duration::from_millis(10))
+        .max_interval(Duration::from_millis(20))
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_stream_submissions() {
+    let server = MockServer::start();
+    server.route(
+        "GET",
+        "/r/rust/new.json",
+        vec![
+            listing(&[submission("b"), submission("a")], None),
+            listing(&[submission("b"), submission("a")], None),
+            listing(&[submission("c"), submission("b"), submission("a")], None),
+        ],
+    );
+    let subreddit = Subreddit::new("rust").base_url(&server.url);
+
+    let mut posts = subreddit.stream_submissions(stream_options());
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        ids.push(next(&mut posts).await.unwrap().unwrap().id);
+    }
+    assert_eq!(ids, ["a", "b", "c"]);
+    assert_eq!(server.requests_to("/r/rust/new.json").len(), 3);
+
+    let server = MockServer::start();
+    server.route(
+        "GET",
+        "/r/rust/new.json",
+        vec![
+            Response::json(503, r#"{"message": "Service Unavailable", "error": 503}"#),
+            listing(&[submission("b"), submission("a")], None),
+            listing(&[submission("c"), submission("b"), submission("a")], None),
+        ],
+    );
+    let subreddit = Subreddit::new("rust")
+        .base_url(&server.url)
+        .retry_policy(RetryPolicy::none());
+
+    // The backlog is skipped even though the first poll failed.
+    let mut posts = subreddit.stream_submissions(stream_options().skip_existing(true));
+    let failed = next(&mut posts).await.unwrap();
+    assert!(matches!(failed, Err(RouxError::ServerError(_))));
+    let post = next(&mut posts).await.unwrap();
+    assert_eq!(post.unwrap().id, "c");
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_stream_comments() {
+    let server = MockServer::start();
+    server.route(
+        "GET",
+        "/r/rust+golang/comments.json",
+        vec![
+            listing(&[comment("b", "t3_x"), comment("a", "t3_x")], None),
+            Response::json(503, r#"{"message": "Service Unavailable", "error": 503}"#),
+            listing(&[comment("c", "t3_y"), comment("b", "t3_x")], None),
+        ],
+    );
+    let subreddit = Subreddit::new("rust+golang")
+        .base_url(&server.url)
+        .retry_policy(RetryPolicy::none());
+
+    let mut stream = subreddit.stream_comments(stream_options());
+    let mut comments = Vec::new();
+    for _ in 0..4 {
+        comments.push(next(&mut stream).await.unwrap());
+    }
+
+    assert_eq!(comments[0].as_ref().unwrap().name.as_deref(), Some("t1_a"));
+    assert_eq!(comments[1].as_ref().unwrap().name.as_deref(), Some("t1_b"));
+    // The failed poll is yielded and the stream carries on.
+    assert!(matches!(comments[2], Err(RouxError::ServerError(_))));
+    assert_eq!(comments[3].as_ref().unwrap().name.as_deref(), Some("t1_c"));
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_stream_inbox() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route(
+            "GET",
+            "/message/unread/.json",
+            vec![
+                listing(
+                    &[
+                        message("c", "username_mention", true),
+                        message("b", "unknown", false),
+                        message("a", "comment_reply", true),
+                    ],
+                    None,
+                ),
+                listing(&[message("d", "comment_reply", true)], None),
+            ],
+        )
+        .route(
+            "POST",
+            "/api/read_message/.json",
+            vec![Response::json(200, "{}")],
+        );
+
+    let me = reddit(&server).login().await.unwrap();
+    let inbox = InboxOptions::new().was_comment(true).mark_read(true);
+    let mut stream = me.stream_inbox(stream_options(), inbox);
+
+    // Items are marked read as they are yielded, not when they are fetched.
+    let first = next(&mut stream).await.unwrap();
+    assert_eq!(first.unwrap().fullname().as_deref(), Some("t1_a"));
+    assert_eq!(server.requests_to("/api/read_message/.json").len(), 1);
+
+    let mut names = Vec::new();
+    for _ in 0..2 {
+        names.push(
+            next(&mut stream)
+                .await
+                .unwrap()
+                .unwrap()
+                .fullname()
+                .unwrap(),
+        );
+    }
+    assert_eq!(names, ["t1_c", "t1_d"]);
+
+    let requests = server.requests_to("/api/read_message/.json");
+    let ids: Vec<&str> = requests.iter().map(|request| &request.body[..]).collect();
+    assert_eq!(ids, ["id=t1_a", "id=t1_c", "id=t1_d"]);
+}
+
+#[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+async fn test_stream_inbox_mark_failed() {
+    let server = MockServer::start();
+    server
+        .route(
+            "POST",
+            "/api/v1/access_token/.json",
+            vec![token("first", 3600)],
+        )
+        .route(
+            "GET",
+            "/message/unread/.json",
+            vec![listing(&[message("a", "comment_reply", true)], None)],
+        )
+        .route(
+            "POST",
+            "/api/read_message/.json",
+            vec![
+                Response::json(503, r#"{"message": "Service Unavailable", "error": 503}"#),
+                Response::json(200, "{}"),
+            ],
+        );
+
+    let me = reddit(&server).login().await.unwrap();
+    let inbox = InboxOptions::new().mark_read(true);
+    let mut stream = me.stream_inbox(stream_options(), inbox);
+
+    // The item is still unread, so the next poll yields it again.
+    let failed = next(&mut stream).await.unwrap();
+    assert!(matches!(failed, Err(RouxError::ServerError(_))));
+    let retried = next(&mut stream).await.unwrap();
+    assert_eq!(retried.unwrap().fullname().as_deref(), Some("t1_a"));
+    assert_eq!(server.requests_to("/api/read_message/.json").len(), 2);
+}
@@ -8,9 +8,8 @@ extern crate tokio;
 mod tests {
     use std::env;
 
-    use roux::saved::SavedData;
     use roux::util::FeedOption;
-    use roux::Reddit;
+    use roux::{Reddit, Thing};
     #[cfg(not(feature = "blocking"))]
     use tokio;
 
@@ -42,9 +41,10 @@ mod tests {
         let options = FeedOption::new().limit(5);
 
         let saved1 = me.saved(None).await.unwrap();
-        let last_child_id1 = match &saved1.data.children.last().unwrap().data {
-            SavedData::Comment(comments_data) => comments_data.id.as_ref().unwrap(),
-            SavedData::Submission(submissions_data) => &submissions_data.id,
+        let last_child_id1 = match saved1.data.children.last().unwrap() {
+            Thing::Comment(comments_data) => comments_data.id.as_ref().unwrap(),
+            Thing::Submission(submissions_data) => &submissions_data.id,
+            other => panic!("expected a comment or post, got {:?}", other),
         };
 
         let saved2 = me
@@ -52,9 +52,10 @@ mod tests {
             .await
             .unwrap();
 
-        let last_child_id2 = match &saved2.data.children.last().unwrap().data {
-            SavedData::Comment(comments_data) => comments_data.id.as_ref().unwrap(),
-            SavedData::Submission(submissions_data) => &submissions_data.id,
+        let last_child_id2 = match saved2.data.children.last().unwrap() {
+            Thing::Comment(comments_data) => comments_data.id.as_ref().unwrap(),
+            Thing::Submission(submissions_data) => &submissions_data.id,
+            other => panic!("expected a comment or post, got {:?}", other),
         };
 
         assert_ne!(last_child_id1, last_child_id2);
@@ -95,18 +96,20 @@ mod tests {
         let options = FeedOption::new().limit(5);
 
         let saved1 = me.saved(None).unwrap();
-        let last_child_id1 = match &saved1.data.children.last().unwrap().data {
-            SavedData::Comment(comments_data) => comments_data.id.as_ref().unwrap(),
-            SavedData::Submission(submissions_data) => &submissions_data.id,
+        let last_child_id1 = match saved1.data.children.last().unwrap() {
+            Thing::Comment(comments_data) => comments_data.id.as_ref().unwrap(),
+            Thing::Submission(submissions_data) => &submissions_data.id,
+            other => panic!("expected a comment or post, got {:?}", other),
         };
 
         let saved2 = me
             .saved(Some(options.after(&saved1.data.after.unwrap())))
             .unwrap();
 
-        let last_child_id2 = match &saved2.data.children.last().unwrap().data {
-            SavedData::Comment(comments_data) => comments_data.id.as_ref().unwrap(),
-            SavedData::Submission(submissions_data) => &submissions_data.id,
+        let last_child_id2 = match saved2.data.children.last().unwrap() {
+            Thing::Comment(comments_data) => comments_data.id.as_ref().unwrap(),
+            Thing::Submission(submissions_data) => &submissions_data.id,
+            other => panic!("expected a comment or post, got {:?}", other),
         };
 
         assert_ne!(last_child_id1, last_child_id2);